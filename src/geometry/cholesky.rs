@@ -1,155 +1,278 @@
-use slang_hal::{test_shader_compilation, Shader};
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
 
-fn substitute2(src: &str) -> String {
-    src.replace("DIM", "2")
-        .replace("MAT", "mat2x2<f32>")
-        .replace("IMPORT_PATH", "stensor::cholesky2")
+/// Shader for computing the Cholesky decompositions of batches of 2x2, 3x3, or 4x4 symmetric
+/// positive-definite matrices, and solving the associated linear systems.
+///
+/// The matrices are stored along the third and fourth dimensions of the tensors. Each matrix
+/// `m` is decomposed as `m = l * transpose(l)` where `l` is lower-triangular. Only the
+/// lower-triangular part of `m` is read.
+#[derive(Shader)]
+#[shader(module = "stensor::geometry::cholesky")]
+pub struct Cholesky<B: Backend> {
+    /// Kernel for the Cholesky decompositions of 2x2 matrices.
+    pub cholesky2: GpuFunction<B>,
+    /// Kernel for the Cholesky decompositions of 3x3 matrices.
+    pub cholesky3: GpuFunction<B>,
+    /// Kernel for the Cholesky decompositions of 4x4 matrices.
+    pub cholesky4: GpuFunction<B>,
+    /// Kernel for solving linear systems from the Cholesky decompositions of 2x2 matrices.
+    pub cholesky_solve2: GpuFunction<B>,
+    /// Kernel for solving linear systems from the Cholesky decompositions of 3x3 matrices.
+    pub cholesky_solve3: GpuFunction<B>,
+    /// Kernel for solving linear systems from the Cholesky decompositions of 4x4 matrices.
+    pub cholesky_solve4: GpuFunction<B>,
 }
 
-fn substitute3(src: &str) -> String {
-    src.replace("DIM", "3")
-        .replace("MAT", "mat3x3<f32>")
-        .replace("IMPORT_PATH", "stensor::cholesky3")
+#[derive(ShaderArgs)]
+struct CholeskyArgs<'a, B: Backend> {
+    shape_matrices: Option<&'a B::Buffer<ViewShape>>,
+    shape_l: &'a B::Buffer<ViewShape>,
+    shape_status: Option<&'a B::Buffer<ViewShape>>,
+    shape_b: Option<&'a B::Buffer<ViewShape>>,
+    matrices: Option<B::BufferSlice<'a, f32>>,
+    l: B::BufferSlice<'a, f32>,
+    status: Option<B::BufferSlice<'a, u32>>,
+    b: Option<B::BufferSlice<'a, f32>>,
 }
 
-fn substitute4(src: &str) -> String {
-    src.replace("DIM", "4")
-        .replace("MAT", "mat4x4<f32>")
-        .replace("IMPORT_PATH", "stensor::cholesky4")
+/// Checks that `l` is a batch of square matrices and returns their dimension.
+fn check_shape(l: ViewShape) -> u32 {
+    let [nrows, ncols, _, _] = l.size;
+    assert_eq!(nrows, ncols, "Cholesky: the matrices must be square.");
+    assert!(
+        (2..=4).contains(&nrows),
+        "Cholesky: only 2x2, 3x3, and 4x4 matrices are supported."
+    );
+    nrows
 }
 
-#[derive(Shader)]
-#[shader(src = "cholesky.wgsl", src_fn = "substitute2")]
-/// Shader for computing the Cholesky decomposition of a symmetric-definite-positive 2x2 matrix.
-pub struct WgCholesky2;
+impl<B: Backend> Cholesky<B> {
+    /// Launches the kernel computing the Cholesky decomposition of each matrix of `matrices`.
+    ///
+    /// The lower-triangular factors are written to `l`, which has the same shape as `matrices`
+    /// and may alias it. Their upper-triangular parts are set to zero.
+    ///
+    /// One value per matrix is written to `status`, of shape `[1, 1, nmats, ncubes]`. It is zero
+    /// if the decomposition succeeded. Otherwise, the matrix isn’t positive-definite (or contains
+    /// NaNs), its factor is only partially computed, and `status - 1` is the index of the column
+    /// where the decomposition stopped.
+    pub fn launch_factor<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        l: impl Into<GpuTensorView<'a, f32, B>>,
+        status: impl Into<GpuTensorView<'a, u32, B>>,
+        matrices: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let l = l.into();
+        let status = status.into();
+        let matrices = matrices.into();
+        let dim = check_shape(l.shape());
+        let [_, _, nmats, ncubes] = l.shape().size;
+        assert_eq!(
+            l.shape().size,
+            matrices.shape().size,
+            "Cholesky: the output and input shapes must match."
+        );
+        assert_eq!(
+            status.shape().size,
+            [1, 1, nmats, ncubes],
+            "Cholesky: the status must contain one value per matrix."
+        );
 
-#[derive(Shader)]
-#[shader(src = "cholesky.wgsl", src_fn = "substitute3")]
-/// Shader for computing the Cholesky decomposition of a symmetric-definite-positive 2x2 matrix.
-pub struct WgCholesky3;
+        let function = match dim {
+            2 => &self.cholesky2,
+            3 => &self.cholesky3,
+            _ => &self.cholesky4,
+        };
 
-#[derive(Shader)]
-#[shader(src = "cholesky.wgsl", src_fn = "substitute4")]
-/// Shader for computing the Cholesky decomposition of a symmetric-definite-positive 2x2 matrix.
-pub struct WgCholesky4;
+        shapes.insert(backend, matrices.shape())?;
+        shapes.insert(backend, l.shape())?;
+        shapes.insert(backend, status.shape())?;
+        let args = CholeskyArgs {
+            shape_matrices: shapes.get(matrices.shape()),
+            shape_l: shapes.get(l.shape()).unwrap_or_else(|| unreachable!()),
+            shape_status: shapes.get(status.shape()),
+            shape_b: None,
+            matrices: Some(matrices.buffer()),
+            l: l.buffer(),
+            status: Some(status.buffer()),
+            b: None,
+        };
+        function.launch_capped(backend, pass, &args, nmats * ncubes)
+    }
 
-test_shader_compilation!(WgCholesky2);
-test_shader_compilation!(WgCholesky3);
-test_shader_compilation!(WgCholesky4);
+    /// Launches the kernel solving `m * x = b` for each matrix `m` decomposed by
+    /// [`Self::launch_factor`] into `l`.
+    ///
+    /// Each column of `b` is a right-hand side, and `b` is overwritten by the solutions. Its
+    /// matrices are associated with the matrices of `l` with the same index along the third and
+    /// fourth dimensions. The solutions are unspecified for matrices with a non-zero status.
+    pub fn launch_solve<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        l: impl Into<GpuTensorView<'a, f32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let l = l.into();
+        let b = b.into();
+        let dim = check_shape(l.shape());
+        let [b_nrows, b_ncols, b_nmats, b_ncubes] = b.shape().size;
+        assert_eq!(
+            [b_nrows, b_nmats, b_ncubes],
+            [dim, l.shape().size[2], l.shape().size[3]],
+            "Cholesky: dimension mismatch between the decompositions and the right-hand sides."
+        );
+
+        let function = match dim {
+            2 => &self.cholesky_solve2,
+            3 => &self.cholesky_solve3,
+            _ => &self.cholesky_solve4,
+        };
+
+        shapes.insert(backend, l.shape())?;
+        shapes.insert(backend, b.shape())?;
+        let args = CholeskyArgs {
+            shape_matrices: None,
+            shape_l: shapes.get(l.shape()).unwrap_or_else(|| unreachable!()),
+            shape_status: None,
+            shape_b: shapes.get(b.shape()),
+            matrices: None,
+            l: l.buffer(),
+            status: None,
+            b: Some(b.buffer()),
+        };
+        function.launch_capped(backend, pass, &args, b_ncols * b_nmats * b_ncubes)
+    }
+}
 
 #[cfg(test)]
 mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::TensorBuilder;
     use approx::assert_relative_eq;
-    use naga_oil::compose::Composer;
-    use nalgebra::{DVector, Matrix2, Matrix4, Matrix4x3};
-    use slang_hal::gpu::GpuInstance;
-    use slang_hal::kernel::{CommandEncoderExt, KernelDispatch};
-    use crate::tensor::GpuTensor;
-    use slang_hal::Shader;
-    use wgpu::BufferUsages;
-    use {
-        naga_oil::compose::NagaModuleDescriptor,
-        wgpu::{ComputePipeline, Device},
-    };
-
-    pub fn test_pipeline<S: Shader>(
-        device: &Device,
-        substitute: fn(&str) -> String,
-    ) -> ComputePipeline {
-        let test_kernel = r#"
-    @group(0) @binding(0)
-    var<storage, read_write> in: array<MAT>;
-    @group(0) @binding(1)
-    var<storage, read_write> out: array<MAT>;
-
-    @compute @workgroup_size(1, 1, 1)
-    fn test(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
-        let i = invocation_id.x;
-        out[i] = cholesky(in[i]);
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_cholesky_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_cholesky_generic(backend).await;
     }
-            "#;
-
-        let src = substitute(&format!("{}\n{}", S::src(), test_kernel));
-        let module = Composer::default()
-            .make_naga_module(NagaModuleDescriptor {
-                source: &src,
-                file_path: "",
-                ..Default::default()
-            })
-            .unwrap();
-        slang_hal::utils::load_module(device, "test", module)
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_cholesky_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_cholesky_generic(backend).await;
     }
 
-    macro_rules! gen_test {
-        ($name: ident, $kernel: ident, $mat: ident, $substitute: ident, $dim: expr) => {
-            #[futures_test::test]
-            #[serial_test::serial]
-            async fn $name() {
-                let gpu = GpuInstance::new().await.unwrap();
-                let chol = test_pipeline::<super::$kernel>(gpu.device(), super::$substitute);
-                let mut encoder = gpu.device().create_command_encoder(&Default::default());
-
-                type Mat = $mat<f32>;
-
-                const LEN: usize = 345;
-                let mut matrices: DVector<Mat> = DVector::new_random(LEN);
-                for i in 0..matrices.len() {
-                    let sdp = matrices[i].fixed_rows::<$dim>(0).transpose()
-                        * matrices[i].fixed_rows::<$dim>(0);
-                    matrices[i].fixed_rows_mut::<$dim>(0).copy_from(&sdp);
-                }
-
-                let inputs = GpuTensor::vector(gpu.device(), &matrices, BufferUsages::STORAGE);
-                let result: GpuTensor<Mat> = GpuTensor::vector_uninit(
-                    gpu.device(),
-                    matrices.len() as u32,
-                    BufferUsages::STORAGE | BufferUsages::COPY_SRC,
-                );
-                let staging: GpuTensor<Mat> = GpuTensor::vector_uninit(
-                    gpu.device(),
-                    matrices.len() as u32,
-                    BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-                );
+    async fn gpu_cholesky_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let cholesky = super::Cholesky::from_backend(&backend, &compiler).unwrap();
+
+        const LEN: usize = 345;
+        const NRHS: usize = 2;
+
+        for dim in 2..=4 {
+            println!("Checking {dim}x{dim} matrices.");
+            let matrices: Vec<_> = (0..LEN)
+                .map(|k| {
+                    let m = DMatrix::<f32>::new_random(dim, dim);
+                    let spd = m.tr_mul(&m) + DMatrix::identity(dim, dim);
+                    // Make some of the matrices negative-definite.
+                    if k % 10 == 0 { -spd } else { spd }
+                })
+                .collect();
+            let rhs: Vec<_> = (0..LEN)
+                .map(|_| DMatrix::<f32>::new_random(dim, NRHS))
+                .collect();
+            let matrices_data: Vec<f32> = matrices.iter().flat_map(|m| m.iter().copied()).collect();
+            let rhs_data: Vec<f32> = rhs.iter().flat_map(|b| b.iter().copied()).collect();
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let d = dim as u32;
+            let gpu_matrices = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_init(&backend, &matrices_data)
+                .unwrap();
+            let gpu_l = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_status = TensorBuilder::tensor([1, 1, LEN as u32, 1], usage)
+                .build_uninit::<u32, _>(&backend)
+                .unwrap();
+            let gpu_rhs = TensorBuilder::tensor([d, NRHS as u32, LEN as u32, 1], usage)
+                .build_init(&backend, &rhs_data)
+                .unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            cholesky
+                .launch_factor(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    &gpu_l,
+                    &gpu_status,
+                    &gpu_matrices,
+                )
+                .unwrap();
+            cholesky
+                .launch_solve(&backend, &mut shapes, &mut pass, &gpu_l, &gpu_rhs)
+                .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
 
-                // Dispatch the test.
-                let mut pass = encoder.compute_pass("test", None);
-                KernelDispatch::new(gpu.device(), &mut pass, &chol)
-                    .bind0([inputs.buffer(), result.buffer()])
-                    .dispatch(matrices.len() as u32);
-                drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
-
-                // Submit.
-                staging.copy_from(&mut encoder, &result);
-                gpu.queue().submit(Some(encoder.finish()));
-
-                // Check the result is correct.
-                let gpu_result = staging.read(gpu.device()).await.unwrap();
-
-                let mut allowed_fails = 0;
-
-                for (m, chol) in matrices.iter().zip(gpu_result.iter()) {
-                    if let Some(chol_cpu) = m.fixed_rows::<$dim>(0).cholesky() {
-                        let chol = chol.fixed_rows::<$dim>(0).into_owned();
-
-                        if allowed_fails == matrices.len() / 100 {
-                            assert_relative_eq!(chol_cpu.unpack_dirty(), chol, epsilon = 1.0e-3);
-                        } else if !approx::relative_eq!(
-                            chol_cpu.unpack_dirty(),
-                            chol,
-                            epsilon = 1.0e-3
-                        ) {
-                            allowed_fails += 1;
-                        }
-                    }
-                }
-
-                println!("Num fails: {}/{}", allowed_fails, matrices.len());
+            let mut l_data = vec![0.0; matrices_data.len()];
+            let mut status_data = vec![0; LEN];
+            let mut solutions_data = vec![0.0; rhs_data.len()];
+            backend
+                .slow_read_buffer(gpu_l.buffer(), l_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_status.buffer(), status_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_rhs.buffer(), solutions_data.as_mut_slice())
+                .await
+                .unwrap();
+
+            for k in 0..LEN {
+                let Some(chol_cpu) = matrices[k].clone().cholesky() else {
+                    // The first diagonal element of a negative-definite matrix is negative.
+                    assert_eq!(status_data[k], 1);
+                    continue;
+                };
+                assert_eq!(status_data[k], 0);
+
+                let l_gpu = &l_data[k * dim * dim..][..dim * dim];
+                let l_gpu = DMatrix::from_column_slice(dim, dim, l_gpu);
+                assert_relative_eq!(l_gpu, chol_cpu.l(), epsilon = 1.0e-4);
+
+                let solution_cpu = chol_cpu.solve(&rhs[k]);
+                let solution_gpu = DMatrix::from_column_slice(
+                    dim,
+                    NRHS,
+                    &solutions_data[k * dim * NRHS..][..dim * NRHS],
+                );
+                assert_relative_eq!(solution_gpu, solution_cpu, epsilon = 1.0e-3);
             }
-        };
+        }
     }
-
-    gen_test!(gpu_cholesky2, WgCholesky2, Matrix2, substitute2, 2);
-    // NOTE: for the 3x3 test we need Matrix4x3 to account for the WGSL mat4x3 padding/alignment.
-    gen_test!(gpu_cholesky3, WgCholesky3, Matrix4x3, substitute3, 3);
-    gen_test!(gpu_cholesky4, WgCholesky4, Matrix4, substitute4, 4);
 }
@@ -0,0 +1,223 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// Shader for computing the eigendecompositions of batches of 2x2, 3x3, or 4x4 symmetric
+/// matrices.
+///
+/// The matrices are stored along the third and fourth dimensions of the tensors. The
+/// decompositions are computed with the cyclic Jacobi method, and only the lower-triangular part
+/// of each matrix is read. Unlike nalgebra's
+/// [`SymmetricEigen`](nalgebra::linalg::SymmetricEigen), the eigenvalues are sorted in increasing
+/// order.
+#[derive(Shader)]
+#[shader(module = "stensor::geometry::eig")]
+pub struct SymmetricEigen<B: Backend> {
+    /// Kernel for the eigendecompositions of 2x2 symmetric matrices.
+    pub symmetric_eigen2: GpuFunction<B>,
+    /// Kernel for the eigendecompositions of 3x3 symmetric matrices.
+    pub symmetric_eigen3: GpuFunction<B>,
+    /// Kernel for the eigendecompositions of 4x4 symmetric matrices.
+    pub symmetric_eigen4: GpuFunction<B>,
+}
+
+#[derive(ShaderArgs)]
+struct SymmetricEigenArgs<'a, B: Backend> {
+    shape_matrices: &'a B::Buffer<ViewShape>,
+    shape_eigenvalues: &'a B::Buffer<ViewShape>,
+    shape_eigenvectors: &'a B::Buffer<ViewShape>,
+    matrices: B::BufferSlice<'a, f32>,
+    eigenvalues: B::BufferSlice<'a, f32>,
+    eigenvectors: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> SymmetricEigen<B> {
+    /// Launches the kernel computing the eigendecomposition of each matrix of `matrices`.
+    ///
+    /// For `n`x`n` matrices, the sorted eigenvalues are written to `eigenvalues`, of shape
+    /// `[n, 1, nmats, ncubes]`. The associated unit eigenvectors are written to the columns of
+    /// `eigenvectors`, which has the same shape as `matrices` and may alias it.
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        eigenvalues: impl Into<GpuTensorView<'a, f32, B>>,
+        eigenvectors: impl Into<GpuTensorView<'a, f32, B>>,
+        matrices: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let eigenvalues = eigenvalues.into();
+        let eigenvectors = eigenvectors.into();
+        let matrices = matrices.into();
+        let [nrows, ncols, nmats, ncubes] = matrices.shape().size;
+        assert_eq!(nrows, ncols, "SymmetricEigen: the matrices must be square.");
+        assert_eq!(
+            eigenvectors.shape().size,
+            matrices.shape().size,
+            "SymmetricEigen: the eigenvectors and input shapes must match."
+        );
+        assert_eq!(
+            eigenvalues.shape().size,
+            [nrows, 1, nmats, ncubes],
+            "SymmetricEigen: the eigenvalues must contain one vector per matrix."
+        );
+
+        let function = match nrows {
+            2 => &self.symmetric_eigen2,
+            3 => &self.symmetric_eigen3,
+            4 => &self.symmetric_eigen4,
+            _ => panic!("SymmetricEigen: only 2x2, 3x3, and 4x4 matrices are supported."),
+        };
+
+        shapes.insert(backend, matrices.shape())?;
+        shapes.insert(backend, eigenvalues.shape())?;
+        shapes.insert(backend, eigenvectors.shape())?;
+        let shape = |s: ViewShape| shapes.get(s).unwrap_or_else(|| unreachable!());
+        let args = SymmetricEigenArgs {
+            shape_matrices: shape(matrices.shape()),
+            shape_eigenvalues: shape(eigenvalues.shape()),
+            shape_eigenvectors: shape(eigenvectors.shape()),
+            matrices: matrices.buffer(),
+            eigenvalues: eigenvalues.buffer(),
+            eigenvectors: eigenvectors.buffer(),
+        };
+        function.launch_capped(backend, pass, &args, nmats * ncubes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::TensorBuilder;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_symmetric_eigen_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_symmetric_eigen_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_symmetric_eigen_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_symmetric_eigen_generic(backend).await;
+    }
+
+    /// A symmetric matrix with the given eigenvalues and random eigenvectors.
+    fn with_eigenvalues(eigenvalues: &[f32]) -> DMatrix<f32> {
+        let dim = eigenvalues.len();
+        let q = DMatrix::<f32>::new_random(dim, dim)
+            .add_scalar(-0.5)
+            .qr()
+            .q();
+        &q * DMatrix::from_diagonal(&DVector::from_column_slice(eigenvalues)) * q.transpose()
+    }
+
+    async fn gpu_symmetric_eigen_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let eigen = super::SymmetricEigen::from_backend(&backend, &compiler).unwrap();
+
+        const LEN: usize = 345;
+
+        for dim in 2..=4 {
+            println!("Checking {dim}x{dim} matrices.");
+            let mut matrices: Vec<_> = (0..LEN)
+                .map(|_| {
+                    let m = DMatrix::<f32>::new_random(dim, dim).add_scalar(-0.5);
+                    &m + m.transpose()
+                })
+                .collect();
+            // Degenerate cases.
+            matrices[0] = DMatrix::zeros(dim, dim);
+            matrices[1] = DMatrix::identity(dim, dim);
+            matrices[2] = DMatrix::from_diagonal(&DVector::from_fn(dim, |i, _| -(i as f32)));
+            // Repeated eigenvalues.
+            matrices[3] = with_eigenvalues(&vec![2.0; dim]);
+            let mut repeated = vec![1.0; dim];
+            repeated[0] = -3.0;
+            matrices[4] = with_eigenvalues(&repeated);
+            // Rank-deficient.
+            let mut rank_deficient = vec![0.0; dim];
+            rank_deficient[dim - 1] = 5.0;
+            matrices[5] = with_eigenvalues(&rank_deficient);
+
+            let matrices_data: Vec<f32> = matrices.iter().flat_map(|m| m.iter().copied()).collect();
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let d = dim as u32;
+            let gpu_matrices = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_init(&backend, &matrices_data)
+                .unwrap();
+            let gpu_eigenvalues = TensorBuilder::tensor([d, 1, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_eigenvectors = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            eigen
+                .launch(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    &gpu_eigenvalues,
+                    &gpu_eigenvectors,
+                    &gpu_matrices,
+                )
+                .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut eigenvalues_data = vec![0.0; dim * LEN];
+            let mut eigenvectors_data = vec![0.0; matrices_data.len()];
+            backend
+                .slow_read_buffer(gpu_eigenvalues.buffer(), eigenvalues_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_eigenvectors.buffer(), eigenvectors_data.as_mut_slice())
+                .await
+                .unwrap();
+
+            for (k, m) in matrices.iter().enumerate() {
+                let eigenvalues = DVector::from_column_slice(&eigenvalues_data[k * dim..][..dim]);
+                let eigenvectors = DMatrix::from_column_slice(
+                    dim,
+                    dim,
+                    &eigenvectors_data[k * dim * dim..][..dim * dim],
+                );
+
+                let mut eigenvalues_cpu = m.clone().symmetric_eigen().eigenvalues;
+                eigenvalues_cpu
+                    .as_mut_slice()
+                    .sort_by(|a, b| a.partial_cmp(b).unwrap());
+                assert_relative_eq!(eigenvalues, eigenvalues_cpu, epsilon = 1.0e-4);
+
+                // The eigenvectors aren’t unique for repeated eigenvalues, so check that they form
+                // an orthonormal basis diagonalizing the matrix instead.
+                assert_relative_eq!(
+                    eigenvectors.tr_mul(&eigenvectors),
+                    DMatrix::identity(dim, dim),
+                    epsilon = 1.0e-4
+                );
+                let recomposed =
+                    &eigenvectors * DMatrix::from_diagonal(&eigenvalues) * eigenvectors.transpose();
+                assert_relative_eq!(recomposed, *m, epsilon = 1.0e-4);
+            }
+        }
+    }
+}
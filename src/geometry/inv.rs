@@ -1,8 +1,219 @@
-use slang_hal::Shader;
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
 
+/// Shader for computing the inverses and determinants of batches of 2x2, 3x3, or 4x4 matrices.
+///
+/// The matrices are stored along the third and fourth dimensions of the tensors. The inverses
+/// are computed from the adjugate matrices, so they are fast but less accurate than a solve based
+/// on [`Lu`](crate::geometry::Lu) for ill-conditioned matrices.
 #[derive(Shader)]
-#[shader(src = "inv.wgsl")]
-/// Shader exposing small matrix inverses.
-pub struct WgInv;
+#[shader(module = "stensor::geometry::inverse")]
+pub struct Inverse<B: Backend> {
+    /// Kernel for the inverses of 2x2 matrices.
+    pub inverse2: GpuFunction<B>,
+    /// Kernel for the inverses of 3x3 matrices.
+    pub inverse3: GpuFunction<B>,
+    /// Kernel for the inverses of 4x4 matrices.
+    pub inverse4: GpuFunction<B>,
+}
 
-slang_hal::test_shader_compilation!(WgInv);
+#[derive(ShaderArgs)]
+struct InverseArgs<'a, B: Backend> {
+    shape_matrices: &'a B::Buffer<ViewShape>,
+    shape_inverses: &'a B::Buffer<ViewShape>,
+    shape_determinants: &'a B::Buffer<ViewShape>,
+    shape_singular: &'a B::Buffer<ViewShape>,
+    matrices: B::BufferSlice<'a, f32>,
+    inverses: B::BufferSlice<'a, f32>,
+    determinants: B::BufferSlice<'a, f32>,
+    singular: B::BufferSlice<'a, u32>,
+}
+
+impl<B: Backend> Inverse<B> {
+    /// Launches the kernel computing the inverse and the determinant of each matrix of
+    /// `matrices`.
+    ///
+    /// The inverses are written to `inverses`, which has the same shape as `matrices` and may
+    /// alias it. The determinants are written to `determinants`, of shape
+    /// `[1, 1, nmats, ncubes]`.
+    ///
+    /// One value per matrix is written to `singular`, of shape `[1, 1, nmats, ncubes]`. It is `1`
+    /// if the matrix is singular (its determinant is zero, or its inverse isn’t finite), in which
+    /// case its inverse is unspecified, and `0` otherwise.
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        inverses: impl Into<GpuTensorView<'a, f32, B>>,
+        determinants: impl Into<GpuTensorView<'a, f32, B>>,
+        singular: impl Into<GpuTensorView<'a, u32, B>>,
+        matrices: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let inverses = inverses.into();
+        let determinants = determinants.into();
+        let singular = singular.into();
+        let matrices = matrices.into();
+        let [nrows, ncols, nmats, ncubes] = matrices.shape().size;
+        assert_eq!(nrows, ncols, "Inverse: the matrices must be square.");
+        assert_eq!(
+            inverses.shape().size,
+            matrices.shape().size,
+            "Inverse: the output and input shapes must match."
+        );
+        assert_eq!(
+            determinants.shape().size,
+            [1, 1, nmats, ncubes],
+            "Inverse: the determinants must contain one value per matrix."
+        );
+        assert_eq!(
+            singular.shape().size,
+            [1, 1, nmats, ncubes],
+            "Inverse: the singular mask must contain one value per matrix."
+        );
+
+        let function = match nrows {
+            2 => &self.inverse2,
+            3 => &self.inverse3,
+            4 => &self.inverse4,
+            _ => panic!("Inverse: only 2x2, 3x3, and 4x4 matrices are supported."),
+        };
+
+        shapes.insert(backend, matrices.shape())?;
+        shapes.insert(backend, inverses.shape())?;
+        shapes.insert(backend, determinants.shape())?;
+        shapes.insert(backend, singular.shape())?;
+        let shape = |s: ViewShape| shapes.get(s).unwrap_or_else(|| unreachable!());
+        let args = InverseArgs {
+            shape_matrices: shape(matrices.shape()),
+            shape_inverses: shape(inverses.shape()),
+            shape_determinants: shape(determinants.shape()),
+            shape_singular: shape(singular.shape()),
+            matrices: matrices.buffer(),
+            inverses: inverses.buffer(),
+            determinants: determinants.buffer(),
+            singular: singular.buffer(),
+        };
+        function.launch_capped(backend, pass, &args, nmats * ncubes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::TensorBuilder;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_inverse_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_inverse_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_inverse_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_inverse_generic(backend).await;
+    }
+
+    async fn gpu_inverse_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let inverse = super::Inverse::from_backend(&backend, &compiler).unwrap();
+
+        const LEN: usize = 345;
+
+        for dim in 2..=4 {
+            println!("Checking {dim}x{dim} matrices.");
+            let mut matrices: Vec<_> = (0..LEN)
+                .map(|_| {
+                    // Keep the matrices well-conditioned by making them diagonally dominant.
+                    DMatrix::<f32>::new_random(dim, dim).add_scalar(-0.5)
+                        + DMatrix::identity(dim, dim) * dim as f32
+                })
+                .collect();
+            // Singular matrices. A zero column ensures the computed determinant is exactly zero.
+            matrices[0] = DMatrix::zeros(dim, dim);
+            matrices[1].column_mut(0).fill(0.0);
+
+            let matrices_data: Vec<f32> = matrices.iter().flat_map(|m| m.iter().copied()).collect();
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let d = dim as u32;
+            let gpu_matrices = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_init(&backend, &matrices_data)
+                .unwrap();
+            let gpu_inverses = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_determinants = TensorBuilder::tensor([1, 1, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_singular = TensorBuilder::tensor([1, 1, LEN as u32, 1], usage)
+                .build_uninit::<u32, _>(&backend)
+                .unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            inverse
+                .launch(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    &gpu_inverses,
+                    &gpu_determinants,
+                    &gpu_singular,
+                    &gpu_matrices,
+                )
+                .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut inverses_data = vec![0.0; matrices_data.len()];
+            let mut determinants_data = vec![0.0; LEN];
+            let mut singular_data = vec![0; LEN];
+            backend
+                .slow_read_buffer(gpu_inverses.buffer(), inverses_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_determinants.buffer(), determinants_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_singular.buffer(), singular_data.as_mut_slice())
+                .await
+                .unwrap();
+
+            for (k, m) in matrices.iter().enumerate() {
+                assert_relative_eq!(determinants_data[k], m.determinant(), epsilon = 1.0e-3);
+
+                if k < 2 {
+                    assert_eq!(determinants_data[k], 0.0);
+                    assert_eq!(singular_data[k], 1);
+                    continue;
+                }
+
+                assert_eq!(singular_data[k], 0);
+                let inv = DMatrix::from_column_slice(
+                    dim,
+                    dim,
+                    &inverses_data[k * dim * dim..][..dim * dim],
+                );
+                assert_relative_eq!(inv, m.clone().try_inverse().unwrap(), epsilon = 1.0e-4);
+            }
+        }
+    }
+}
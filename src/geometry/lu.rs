@@ -1,188 +1,283 @@
-use nalgebra::{SMatrix, SVector};
-use slang_hal::re_exports::encase::ShaderType;
-use slang_hal::{test_shader_compilation, Shader};
-
-fn substitute2(src: &str) -> String {
-    src.replace("NROWS", "2u")
-        .replace("NCOLS", "2u")
-        .replace("PERM", "vec2<u32>")
-        .replace("MAT", "mat2x2<f32>")
-        .replace("IMPORT_PATH", "stensor::lu2")
-}
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
 
-fn substitute3(src: &str) -> String {
-    src.replace("NROWS", "3u")
-        .replace("NCOLS", "3u")
-        .replace("PERM", "vec3<u32>")
-        .replace("MAT", "mat3x3<f32>")
-        .replace("IMPORT_PATH", "stensor::lu3")
+/// Shader for computing the LU decompositions (with partial pivoting) of batches of 2x2, 3x3, or
+/// 4x4 matrices, and solving the associated linear systems.
+///
+/// The matrices are stored along the third and fourth dimensions of the tensors. The
+/// decomposition follows the same algorithm as nalgebra's [`LU`](nalgebra::linalg::LU): the
+/// lower-triangular factor (without its unit diagonal) and the upper-triangular factor are
+/// stored in the same matrix. The `i`-th pivot indicates that the row `i` was swapped with the
+/// row `pivots[i]` at the `i`-th step of the decomposition.
+#[derive(Shader)]
+#[shader(module = "stensor::geometry::lu")]
+pub struct Lu<B: Backend> {
+    /// Kernel for the LU decompositions of 2x2 matrices.
+    pub lu2: GpuFunction<B>,
+    /// Kernel for the LU decompositions of 3x3 matrices.
+    pub lu3: GpuFunction<B>,
+    /// Kernel for the LU decompositions of 4x4 matrices.
+    pub lu4: GpuFunction<B>,
+    /// Kernel for solving linear systems from the LU decompositions of 2x2 matrices.
+    pub lu_solve2: GpuFunction<B>,
+    /// Kernel for solving linear systems from the LU decompositions of 3x3 matrices.
+    pub lu_solve3: GpuFunction<B>,
+    /// Kernel for solving linear systems from the LU decompositions of 4x4 matrices.
+    pub lu_solve4: GpuFunction<B>,
 }
 
-fn substitute4(src: &str) -> String {
-    src.replace("NROWS", "4u")
-        .replace("NCOLS", "4u")
-        .replace("PERM", "vec4<u32>")
-        .replace("MAT", "mat4x4<f32>")
-        .replace("IMPORT_PATH", "stensor::lu4")
+#[derive(ShaderArgs)]
+struct LuArgs<'a, B: Backend> {
+    shape_matrices: Option<&'a B::Buffer<ViewShape>>,
+    shape_lu: &'a B::Buffer<ViewShape>,
+    shape_pivots: &'a B::Buffer<ViewShape>,
+    shape_b: Option<&'a B::Buffer<ViewShape>>,
+    matrices: Option<B::BufferSlice<'a, f32>>,
+    lu: B::BufferSlice<'a, f32>,
+    pivots: B::BufferSlice<'a, u32>,
+    b: Option<B::BufferSlice<'a, f32>>,
 }
 
-macro_rules! gpu_output_types(
-    ($GpuPermutation: ident, $GpuLU: ident, $R: literal, $C: literal, $Perm: literal) => {
-        /// Structure describing a permutation sequence applied by the LU decomposition.
-        #[derive(ShaderType, Copy, Clone, PartialEq)]
-        #[repr(C)]
-        pub struct $GpuPermutation {
-            /// First permutation indices (row `ia[i]` is permuted with row`ib[i]`].
-            pub ia: SVector<u32, $Perm>,
-            /// Second permutation indices (row `ia[i]` is permuted with row`ib[i]`].
-            pub ib: SVector<u32, $Perm>,
-            /// The number of permutations in `self`. Only the first `len` elements of
-            /// [`Self::ia`] and [`Self::ib`] need to be taken into account.
-            pub len: u32,
-        }
+/// Checks that `lu` is a batch of square matrices, `pivots` a batch of vectors matching it, and
+/// returns the dimension of the matrices.
+fn check_shapes(lu: ViewShape, pivots: ViewShape) -> u32 {
+    let [nrows, ncols, nmats, ncubes] = lu.size;
+    assert_eq!(nrows, ncols, "Lu: the matrices must be square.");
+    assert!(
+        (2..=4).contains(&nrows),
+        "Lu: only 2x2, 3x3, and 4x4 matrices are supported."
+    );
+    assert_eq!(
+        pivots.size,
+        [nrows, 1, nmats, ncubes],
+        "Lu: the pivots must contain one vector per matrix."
+    );
+    nrows
+}
 
-        /// GPU representation of a matrix LU decomposition (with partial pivoting).
-        ///
-        /// See the [nalgebra](https://nalgebra.rs/docs/user_guide/decompositions_and_lapack#lu-with-partial-or-full-pivoting) documentation
-        /// for details on the LU decomposition.
-        #[derive(ShaderType, Copy, Clone, PartialEq)]
-        #[repr(C)]
-        pub struct $GpuLU {
-            /// The LU decomposition where both lower and upper-triangular matrices are stored
-            /// in the same matrix. In particular the diagonal full of `1` of the lower-triangular
-            /// matrix isn’t stored explicitly.
-            pub lu: SMatrix<f32, $R, $C>,
-            /// The row permutations applied during the decomposition.
-            pub p: $GpuPermutation,
-        }
-    }
-);
+impl<B: Backend> Lu<B> {
+    /// Launches the kernel computing the LU decomposition of each matrix of `matrices`.
+    ///
+    /// The combined factors are written to `lu`, which has the same shape as `matrices` and may
+    /// alias it. The row transpositions are written to `pivots`, of shape `[n, 1, nmats, ncubes]`
+    /// for `n`x`n` matrices.
+    pub fn launch_factor<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        lu: impl Into<GpuTensorView<'a, f32, B>>,
+        pivots: impl Into<GpuTensorView<'a, u32, B>>,
+        matrices: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let lu = lu.into();
+        let pivots = pivots.into();
+        let matrices = matrices.into();
+        let dim = check_shapes(lu.shape(), pivots.shape());
+        assert_eq!(
+            lu.shape().size,
+            matrices.shape().size,
+            "Lu: the output and input shapes must match."
+        );
 
-gpu_output_types!(GpuPermutations2, GpuLU2, 2, 2, 2);
-gpu_output_types!(GpuPermutations3, GpuLU3, 3, 3, 3);
-gpu_output_types!(GpuPermutations4, GpuLU4, 4, 4, 4);
+        let function = match dim {
+            2 => &self.lu2,
+            3 => &self.lu3,
+            _ => &self.lu4,
+        };
 
-// TODO: rectangular matrices
-#[derive(Shader)]
-#[shader(src = "lu.wgsl", src_fn = "substitute2")]
-/// Shader for computing the LU decomposition of a 2x2 matrix.
-pub struct WgLU2;
+        shapes.insert(backend, matrices.shape())?;
+        shapes.insert(backend, lu.shape())?;
+        shapes.insert(backend, pivots.shape())?;
+        let args = LuArgs {
+            shape_matrices: shapes.get(matrices.shape()),
+            shape_lu: shapes.get(lu.shape()).unwrap_or_else(|| unreachable!()),
+            shape_pivots: shapes.get(pivots.shape()).unwrap_or_else(|| unreachable!()),
+            shape_b: None,
+            matrices: Some(matrices.buffer()),
+            lu: lu.buffer(),
+            pivots: pivots.buffer(),
+            b: None,
+        };
+        let num_matrices = lu.shape().size[2] * lu.shape().size[3];
+        function.launch_capped(backend, pass, &args, num_matrices)
+    }
 
-#[derive(Shader)]
-#[shader(src = "lu.wgsl", src_fn = "substitute3")]
-/// Shader for computing the LU decomposition of a 3x3 matrix.
-pub struct WgLU3;
+    /// Launches the kernel solving `m * x = b` for each matrix `m` decomposed by
+    /// [`Self::launch_factor`] into `lu` and `pivots`.
+    ///
+    /// Each column of `b` is a right-hand side, and `b` is overwritten by the solutions. Its
+    /// matrices are associated with the matrices of `lu` with the same index along the third and
+    /// fourth dimensions.
+    pub fn launch_solve<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        lu: impl Into<GpuTensorView<'a, f32, B>>,
+        pivots: impl Into<GpuTensorView<'a, u32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let lu = lu.into();
+        let pivots = pivots.into();
+        let b = b.into();
+        let dim = check_shapes(lu.shape(), pivots.shape());
+        let [b_nrows, b_ncols, b_nmats, b_ncubes] = b.shape().size;
+        assert_eq!(
+            [b_nrows, b_nmats, b_ncubes],
+            [dim, lu.shape().size[2], lu.shape().size[3]],
+            "Lu: dimension mismatch between the decompositions and the right-hand sides."
+        );
 
-#[derive(Shader)]
-#[shader(src = "lu.wgsl", src_fn = "substitute4")]
-/// Shader for computing the LU decomposition of a 4x4 matrix.
-pub struct WgLU4;
+        let function = match dim {
+            2 => &self.lu_solve2,
+            3 => &self.lu_solve3,
+            _ => &self.lu_solve4,
+        };
 
-test_shader_compilation!(WgLU2);
-test_shader_compilation!(WgLU3);
-test_shader_compilation!(WgLU4);
+        shapes.insert(backend, lu.shape())?;
+        shapes.insert(backend, pivots.shape())?;
+        shapes.insert(backend, b.shape())?;
+        let args = LuArgs {
+            shape_matrices: None,
+            shape_lu: shapes.get(lu.shape()).unwrap_or_else(|| unreachable!()),
+            shape_pivots: shapes.get(pivots.shape()).unwrap_or_else(|| unreachable!()),
+            shape_b: shapes.get(b.shape()),
+            matrices: None,
+            lu: lu.buffer(),
+            pivots: pivots.buffer(),
+            b: Some(b.buffer()),
+        };
+        function.launch_capped(backend, pass, &args, b_ncols * b_nmats * b_ncubes)
+    }
+}
 
 #[cfg(test)]
 mod test {
-    use super::{GpuLU2, GpuLU3, GpuLU4};
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::TensorBuilder;
     use approx::assert_relative_eq;
-    use naga_oil::compose::Composer;
-    use nalgebra::{DVector, Matrix2, Matrix4, Matrix4x3};
-    use slang_hal::gpu::GpuInstance;
-    use slang_hal::kernel::{CommandEncoderExt, KernelDispatch};
-    use crate::tensor::GpuTensor;
-    use slang_hal::Shader;
-    use wgpu::BufferUsages;
-    use {
-        naga_oil::compose::NagaModuleDescriptor,
-        wgpu::{ComputePipeline, Device},
-    };
-
-    pub fn test_pipeline<S: Shader>(
-        device: &Device,
-        substitute: fn(&str) -> String,
-    ) -> ComputePipeline {
-        let test_kernel = r#"
-    @group(0) @binding(0)
-    var<storage, read_write> in: array<MAT>;
-    @group(0) @binding(1)
-    var<storage, read_write> out: array<LU>;
-
-    @compute @workgroup_size(1, 1, 1)
-    fn test(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
-        let i = invocation_id.x;
-        out[i] = lu(in[i]);
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_lu_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_lu_generic(backend).await;
     }
-            "#;
-
-        let src = substitute(&format!("{}\n{}", S::src(), test_kernel));
-        let module = Composer::default()
-            .make_naga_module(NagaModuleDescriptor {
-                source: &src,
-                file_path: "",
-                ..Default::default()
-            })
-            .unwrap();
-        slang_hal::utils::load_module(device, "test", module)
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_lu_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_lu_generic(backend).await;
     }
 
-    macro_rules! gen_test {
-        ($name: ident, $kernel: ident, $mat: ident, $out: ident, $substitute: ident, $dim: expr) => {
-            #[futures_test::test]
-            #[serial_test::serial]
-            async fn $name() {
-                let gpu = GpuInstance::new().await.unwrap();
-                let lu = test_pipeline::<super::$kernel>(gpu.device(), super::$substitute);
-                let mut encoder = gpu.device().create_command_encoder(&Default::default());
-
-                type Mat = $mat<f32>;
-                type GpuOut = $out;
-
-                const LEN: usize = 345;
-                let mut matrices: DVector<Mat> = DVector::new_random(LEN);
-                for i in 0..matrices.len() {
-                    let sdp = matrices[i].fixed_rows::<$dim>(0).transpose()
-                        * matrices[i].fixed_rows::<$dim>(0);
-                    matrices[i].fixed_rows_mut::<$dim>(0).copy_from(&sdp);
-                }
+    async fn gpu_lu_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let lu = super::Lu::from_backend(&backend, &compiler).unwrap();
 
-                let inputs = GpuTensor::vector(gpu.device(), &matrices, BufferUsages::STORAGE);
-                let result: GpuTensor<GpuOut> = GpuTensor::vector_uninit_encased(
-                    gpu.device(),
-                    matrices.len() as u32,
-                    BufferUsages::STORAGE | BufferUsages::COPY_SRC,
-                );
-                let staging: GpuTensor<GpuOut> = GpuTensor::vector_uninit_encased(
-                    gpu.device(),
-                    matrices.len() as u32,
-                    BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-                );
+        const LEN: usize = 345;
+        const NRHS: usize = 2;
 
-                // Dispatch the test.
-                let mut pass = encoder.compute_pass("test", None);
-                KernelDispatch::new(gpu.device(), &mut pass, &lu)
-                    .bind0([inputs.buffer(), result.buffer()])
-                    .dispatch(matrices.len() as u32);
-                drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+        for dim in 2..=4 {
+            println!("Checking {dim}x{dim} matrices.");
+            let matrices: Vec<_> = (0..LEN)
+                .map(|_| DMatrix::<f32>::new_random(dim, dim).add_scalar(-0.5))
+                .collect();
+            let rhs: Vec<_> = (0..LEN)
+                .map(|_| DMatrix::<f32>::new_random(dim, NRHS))
+                .collect();
+            let matrices_data: Vec<f32> = matrices.iter().flat_map(|m| m.iter().copied()).collect();
+            let rhs_data: Vec<f32> = rhs.iter().flat_map(|b| b.iter().copied()).collect();
 
-                // Submit.
-                staging.copy_from_encased(&mut encoder, &result);
-                gpu.queue().submit(Some(encoder.finish()));
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let d = dim as u32;
+            let gpu_matrices = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_init(&backend, &matrices_data)
+                .unwrap();
+            let gpu_lu = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_pivots = TensorBuilder::tensor([d, 1, LEN as u32, 1], usage)
+                .build_uninit::<u32, _>(&backend)
+                .unwrap();
+            let gpu_rhs = TensorBuilder::tensor([d, NRHS as u32, LEN as u32, 1], usage)
+                .build_init(&backend, &rhs_data)
+                .unwrap();
 
-                // Check the result is correct.
-                let gpu_result = staging.read_encased(gpu.device()).await.unwrap();
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            lu.launch_factor(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gpu_lu,
+                &gpu_pivots,
+                &gpu_matrices,
+            )
+            .unwrap();
+            lu.launch_solve(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gpu_lu,
+                &gpu_pivots,
+                &gpu_rhs,
+            )
+            .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
 
-                for (m, lu) in matrices.iter().zip(gpu_result.iter()) {
-                    let lu_cpu = m.fixed_rows::<$dim>(0).lu();
-                    assert_relative_eq!(lu_cpu.lu_internal(), &lu.lu, epsilon = 1.0e-3);
-                    // TODO: check the permutation vectors
+            let mut lu_data = vec![0.0; matrices_data.len()];
+            let mut pivots_data = vec![0; dim * LEN];
+            let mut solutions_data = vec![0.0; rhs_data.len()];
+            backend
+                .slow_read_buffer(gpu_lu.buffer(), lu_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_pivots.buffer(), pivots_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_rhs.buffer(), solutions_data.as_mut_slice())
+                .await
+                .unwrap();
+
+            for k in 0..LEN {
+                let lu_cpu = matrices[k].clone().lu();
+                let lu_gpu = &lu_data[k * dim * dim..][..dim * dim];
+                let lu_gpu = DMatrix::from_column_slice(dim, dim, lu_gpu);
+                assert_relative_eq!(lu_gpu, lu_cpu.lu_internal(), epsilon = 1.0e-4);
+
+                // Compare the permutations by applying them to the identity.
+                let mut perm_cpu = DMatrix::<f32>::identity(dim, dim);
+                let mut perm_gpu = perm_cpu.clone();
+                lu_cpu.p().permute_rows(&mut perm_cpu);
+                for (i, piv) in pivots_data[k * dim..][..dim].iter().enumerate() {
+                    perm_gpu.swap_rows(i, *piv as usize);
                 }
+                assert_eq!(perm_gpu, perm_cpu);
+
+                let solution_cpu = lu_cpu.solve(&rhs[k]).unwrap();
+                let solution_gpu = DMatrix::from_column_slice(
+                    dim,
+                    NRHS,
+                    &solutions_data[k * dim * NRHS..][..dim * NRHS],
+                );
+                assert_relative_eq!(solution_gpu, solution_cpu, epsilon = 1.0e-3);
             }
-        };
+        }
     }
-
-    gen_test!(gpu_lu2, WgLU2, Matrix2, GpuLU2, substitute2, 2);
-    // NOTE: for the 3x3 test we need Matrix4x3 to account for the WGSL mat4x3 padding/alignment.
-    gen_test!(gpu_lu3, WgLU3, Matrix4x3, GpuLU3, substitute3, 3);
-    gen_test!(gpu_lu4, WgLU4, Matrix4, GpuLU4, substitute4, 4);
 }
@@ -0,0 +1,200 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// Shader for computing the QR decompositions of batches of 2x2, 3x3, or 4x4 matrices.
+///
+/// The matrices are stored along the third and fourth dimensions of the tensors. Each matrix `m`
+/// is decomposed as `m = q * r` with Givens rotations, where `q` is orthogonal and `r` is
+/// upper-triangular. Unlike nalgebra's [`QR`](nalgebra::linalg::QR), the diagonal of `r` is
+/// always non-negative, which makes the decomposition unique for invertible matrices.
+#[derive(Shader)]
+#[shader(module = "stensor::geometry::qr")]
+pub struct Qr<B: Backend> {
+    /// Kernel for the QR decompositions of 2x2 matrices.
+    pub qr2: GpuFunction<B>,
+    /// Kernel for the QR decompositions of 3x3 matrices.
+    pub qr3: GpuFunction<B>,
+    /// Kernel for the QR decompositions of 4x4 matrices.
+    pub qr4: GpuFunction<B>,
+}
+
+#[derive(ShaderArgs)]
+struct QrArgs<'a, B: Backend> {
+    shape_matrices: &'a B::Buffer<ViewShape>,
+    shape_q: &'a B::Buffer<ViewShape>,
+    shape_r: &'a B::Buffer<ViewShape>,
+    matrices: B::BufferSlice<'a, f32>,
+    q: B::BufferSlice<'a, f32>,
+    r: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> Qr<B> {
+    /// Launches the kernel computing the QR decomposition of each matrix of `matrices`.
+    ///
+    /// The orthogonal and upper-triangular factors are written to `q` and `r` respectively. Both
+    /// have the same shape as `matrices` and either may alias it.
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        q: impl Into<GpuTensorView<'a, f32, B>>,
+        r: impl Into<GpuTensorView<'a, f32, B>>,
+        matrices: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let q = q.into();
+        let r = r.into();
+        let matrices = matrices.into();
+        let [nrows, ncols, nmats, ncubes] = matrices.shape().size;
+        assert_eq!(nrows, ncols, "Qr: the matrices must be square.");
+        assert_eq!(
+            q.shape().size,
+            matrices.shape().size,
+            "Qr: the `q` and input shapes must match."
+        );
+        assert_eq!(
+            r.shape().size,
+            matrices.shape().size,
+            "Qr: the `r` and input shapes must match."
+        );
+
+        let function = match nrows {
+            2 => &self.qr2,
+            3 => &self.qr3,
+            4 => &self.qr4,
+            _ => panic!("Qr: only 2x2, 3x3, and 4x4 matrices are supported."),
+        };
+
+        shapes.insert(backend, matrices.shape())?;
+        shapes.insert(backend, q.shape())?;
+        shapes.insert(backend, r.shape())?;
+        let shape = |s: ViewShape| shapes.get(s).unwrap_or_else(|| unreachable!());
+        let args = QrArgs {
+            shape_matrices: shape(matrices.shape()),
+            shape_q: shape(q.shape()),
+            shape_r: shape(r.shape()),
+            matrices: matrices.buffer(),
+            q: q.buffer(),
+            r: r.buffer(),
+        };
+        function.launch_capped(backend, pass, &args, nmats * ncubes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::TensorBuilder;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_qr_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_qr_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_qr_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_qr_generic(backend).await;
+    }
+
+    async fn gpu_qr_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let qr = super::Qr::from_backend(&backend, &compiler).unwrap();
+
+        const LEN: usize = 345;
+        const NUM_DEGENERATE: usize = 3;
+
+        for dim in 2..=4 {
+            println!("Checking {dim}x{dim} matrices.");
+            let mut matrices: Vec<_> = (0..LEN)
+                .map(|_| DMatrix::<f32>::new_random(dim, dim).add_scalar(-0.5))
+                .collect();
+            // Degenerate cases.
+            matrices[0] = DMatrix::zeros(dim, dim);
+            matrices[1] = DMatrix::identity(dim, dim);
+            // Rank-deficient, with a zero first column.
+            matrices[2].column_mut(0).fill(0.0);
+
+            let matrices_data: Vec<f32> = matrices.iter().flat_map(|m| m.iter().copied()).collect();
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let d = dim as u32;
+            let gpu_matrices = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_init(&backend, &matrices_data)
+                .unwrap();
+            let gpu_q = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_r = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            qr.launch(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gpu_q,
+                &gpu_r,
+                &gpu_matrices,
+            )
+            .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut q_data = vec![0.0; matrices_data.len()];
+            let mut r_data = vec![0.0; matrices_data.len()];
+            backend
+                .slow_read_buffer(gpu_q.buffer(), q_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_r.buffer(), r_data.as_mut_slice())
+                .await
+                .unwrap();
+
+            for (k, m) in matrices.iter().enumerate() {
+                let q = DMatrix::from_column_slice(dim, dim, &q_data[k * dim * dim..][..dim * dim]);
+                let r = DMatrix::from_column_slice(dim, dim, &r_data[k * dim * dim..][..dim * dim]);
+
+                assert_relative_eq!(q.tr_mul(&q), DMatrix::identity(dim, dim), epsilon = 1.0e-4);
+                assert_eq!(r, r.upper_triangle());
+                assert!(r.diagonal().iter().all(|x| *x >= 0.0));
+                assert_relative_eq!(&q * &r, *m, epsilon = 1.0e-4);
+
+                // The decomposition is unique for invertible matrices once the signs of the
+                // diagonal of `r` are fixed, so normalize nalgebra’s result the same way. Nearly
+                // singular matrices are skipped since their `q` is too sensitive to rounding.
+                let qr_cpu = m.clone().qr();
+                let mut q_cpu = qr_cpu.q();
+                let mut r_cpu = qr_cpu.r();
+                if k >= NUM_DEGENERATE && r_cpu.diagonal().amin() > 1.0e-2 {
+                    for i in 0..dim {
+                        if r_cpu[(i, i)] < 0.0 {
+                            r_cpu.row_mut(i).neg_mut();
+                            q_cpu.column_mut(i).neg_mut();
+                        }
+                    }
+                    assert_relative_eq!(q, q_cpu, epsilon = 1.0e-3);
+                    assert_relative_eq!(r, r_cpu, epsilon = 1.0e-3);
+                }
+            }
+        }
+    }
+}
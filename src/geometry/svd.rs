@@ -0,0 +1,211 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// Shader for computing the singular value decompositions of batches of 2x2 or 3x3 matrices.
+///
+/// The matrices are stored along the third and fourth dimensions of the tensors. Each matrix `m`
+/// is decomposed as `m = u * diag(s) * vt` where `u` and `vt` are orthogonal and the singular
+/// values `s` are non-negative and sorted in decreasing order.
+#[derive(Shader)]
+#[shader(module = "stensor::geometry::svd")]
+pub struct Svd<B: Backend> {
+    /// Kernel for the SVDs of 2x2 matrices.
+    pub svd2x2: GpuFunction<B>,
+    /// Kernel for the SVDs of 3x3 matrices.
+    pub svd3x3: GpuFunction<B>,
+}
+
+#[derive(ShaderArgs)]
+struct SvdArgs<'a, B: Backend> {
+    shape_matrices: &'a B::Buffer<ViewShape>,
+    shape_u: &'a B::Buffer<ViewShape>,
+    shape_s: &'a B::Buffer<ViewShape>,
+    shape_vt: &'a B::Buffer<ViewShape>,
+    matrices: B::BufferSlice<'a, f32>,
+    u: B::BufferSlice<'a, f32>,
+    s: B::BufferSlice<'a, f32>,
+    vt: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> Svd<B> {
+    /// Launches the kernel computing the SVD of each matrix of `matrices`.
+    ///
+    /// For `n`x`n` matrices, the singular values are written to `s`, of shape
+    /// `[n, 1, nmats, ncubes]`. The orthogonal factors are written to `u` and `vt`, which have
+    /// the same shape as `matrices` and either may alias it.
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        u: impl Into<GpuTensorView<'a, f32, B>>,
+        s: impl Into<GpuTensorView<'a, f32, B>>,
+        vt: impl Into<GpuTensorView<'a, f32, B>>,
+        matrices: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let u = u.into();
+        let s = s.into();
+        let vt = vt.into();
+        let matrices = matrices.into();
+        let [nrows, ncols, nmats, ncubes] = matrices.shape().size;
+        assert_eq!(nrows, ncols, "Svd: the matrices must be square.");
+        assert_eq!(
+            u.shape().size,
+            matrices.shape().size,
+            "Svd: the `u` and input shapes must match."
+        );
+        assert_eq!(
+            vt.shape().size,
+            matrices.shape().size,
+            "Svd: the `vt` and input shapes must match."
+        );
+        assert_eq!(
+            s.shape().size,
+            [nrows, 1, nmats, ncubes],
+            "Svd: the singular values must contain one vector per matrix."
+        );
+
+        let function = match nrows {
+            2 => &self.svd2x2,
+            3 => &self.svd3x3,
+            _ => panic!("Svd: only 2x2 and 3x3 matrices are supported."),
+        };
+
+        shapes.insert(backend, matrices.shape())?;
+        shapes.insert(backend, u.shape())?;
+        shapes.insert(backend, s.shape())?;
+        shapes.insert(backend, vt.shape())?;
+        let shape = |s: ViewShape| shapes.get(s).unwrap_or_else(|| unreachable!());
+        let args = SvdArgs {
+            shape_matrices: shape(matrices.shape()),
+            shape_u: shape(u.shape()),
+            shape_s: shape(s.shape()),
+            shape_vt: shape(vt.shape()),
+            matrices: matrices.buffer(),
+            u: u.buffer(),
+            s: s.buffer(),
+            vt: vt.buffer(),
+        };
+        function.launch_capped(backend, pass, &args, nmats * ncubes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::TensorBuilder;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_svd_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_svd_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_svd_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_svd_generic(backend).await;
+    }
+
+    async fn gpu_svd_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let svd = super::Svd::from_backend(&backend, &compiler).unwrap();
+
+        const LEN: usize = 345;
+
+        for dim in 2..=3 {
+            println!("Checking {dim}x{dim} matrices.");
+            let mut matrices: Vec<_> = (0..LEN)
+                .map(|_| DMatrix::<f32>::new_random(dim, dim).add_scalar(-0.5))
+                .collect();
+            // Degenerate cases.
+            matrices[0] = DMatrix::identity(dim, dim);
+            matrices[1] = -DMatrix::identity(dim, dim);
+            matrices[2] = DMatrix::from_diagonal(&DVector::from_fn(dim, |i, _| i as f32 + 1.0));
+
+            let matrices_data: Vec<f32> = matrices.iter().flat_map(|m| m.iter().copied()).collect();
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let d = dim as u32;
+            let gpu_matrices = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_init(&backend, &matrices_data)
+                .unwrap();
+            let gpu_u = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_s = TensorBuilder::tensor([d, 1, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+            let gpu_vt = TensorBuilder::tensor([d, d, LEN as u32, 1], usage)
+                .build_uninit::<f32, _>(&backend)
+                .unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            svd.launch(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gpu_u,
+                &gpu_s,
+                &gpu_vt,
+                &gpu_matrices,
+            )
+            .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut u_data = vec![0.0; matrices_data.len()];
+            let mut s_data = vec![0.0; dim * LEN];
+            let mut vt_data = vec![0.0; matrices_data.len()];
+            backend
+                .slow_read_buffer(gpu_u.buffer(), u_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_s.buffer(), s_data.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_vt.buffer(), vt_data.as_mut_slice())
+                .await
+                .unwrap();
+
+            for (k, m) in matrices.iter().enumerate() {
+                let u = DMatrix::from_column_slice(dim, dim, &u_data[k * dim * dim..][..dim * dim]);
+                let s = DVector::from_column_slice(&s_data[k * dim..][..dim]);
+                let vt =
+                    DMatrix::from_column_slice(dim, dim, &vt_data[k * dim * dim..][..dim * dim]);
+
+                // The singular vectors aren’t unique, so only compare the singular values with
+                // nalgebra, and check that the factors are orthogonal and recompose the matrix.
+                let s_cpu = m.clone().svd(false, false).singular_values;
+                assert_relative_eq!(s, s_cpu, epsilon = 1.0e-3);
+                assert!(s.iter().all(|x| *x >= 0.0));
+                assert!(s.as_slice().windows(2).all(|w| w[0] >= w[1]));
+                assert_relative_eq!(u.tr_mul(&u), DMatrix::identity(dim, dim), epsilon = 1.0e-3);
+                assert_relative_eq!(
+                    vt.tr_mul(&vt),
+                    DMatrix::identity(dim, dim),
+                    epsilon = 1.0e-3
+                );
+                let recomposed = &u * DMatrix::from_diagonal(&s) * &vt;
+                assert_relative_eq!(recomposed, *m, epsilon = 1.0e-3);
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use nalgebra::{Matrix2, Vector2};
+use nalgebra::{Matrix2, SVD, U2, Vector2};
 
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
@@ -11,3 +11,55 @@ pub struct GpuSvd2 {
     /// Second orthogonal matrix of the SVD.
     pub vt: Matrix2<f32>,
 }
+
+impl GpuSvd2 {
+    /// Rebuilds the matrix this SVD is the decomposition of.
+    pub fn recompose(&self) -> Matrix2<f32> {
+        self.u * Matrix2::from_diagonal(&self.s) * self.vt
+    }
+}
+
+impl From<SVD<f32, U2, U2>> for GpuSvd2 {
+    /// Converts a nalgebra SVD to its GPU representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `svd` was computed without `u` or `v_t`.
+    fn from(svd: SVD<f32, U2, U2>) -> Self {
+        Self {
+            u: svd.u.expect("GpuSvd2: the SVD must contain `u`."),
+            s: svd.singular_values,
+            vt: svd.v_t.expect("GpuSvd2: the SVD must contain `v_t`."),
+        }
+    }
+}
+
+impl From<GpuSvd2> for SVD<f32, U2, U2> {
+    fn from(svd: GpuSvd2) -> Self {
+        SVD {
+            u: Some(svd.u),
+            v_t: Some(svd.vt),
+            singular_values: svd.s,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GpuSvd2;
+    use approx::assert_relative_eq;
+    use nalgebra::{Matrix2, SVD, U2};
+
+    #[test]
+    fn svd2_nalgebra_roundtrip() {
+        let m = Matrix2::<f32>::new_random().add_scalar(-0.5);
+        let svd = m.svd(true, true);
+        let gpu_svd = GpuSvd2::from(svd.clone());
+        let roundtrip: SVD<f32, U2, U2> = gpu_svd.into();
+
+        assert_eq!(roundtrip.u, svd.u);
+        assert_eq!(roundtrip.v_t, svd.v_t);
+        assert_eq!(roundtrip.singular_values, svd.singular_values);
+        assert_relative_eq!(gpu_svd.recompose(), m, epsilon = 1.0e-5);
+    }
+}
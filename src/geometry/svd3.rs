@@ -1,4 +1,4 @@
-use nalgebra::{Matrix4x3, Vector4};
+use nalgebra::{Matrix3, Matrix4x3, SVD, U3, Vector3, Vector4};
 
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
@@ -13,3 +13,79 @@ pub struct GpuSvd3 {
     /// Second orthogonal matrix of the SVD.
     vt: Matrix4x3<f32>,
 }
+
+impl GpuSvd3 {
+    /// Creates the GPU representation of the SVD `u * diag(s) * vt`.
+    pub fn new(u: Matrix3<f32>, s: Vector3<f32>, vt: Matrix3<f32>) -> Self {
+        Self {
+            u: u.insert_row(3, 0.0),
+            s: s.push(0.0),
+            vt: vt.insert_row(3, 0.0),
+        }
+    }
+
+    /// First orthogonal matrix of the SVD.
+    pub fn u(&self) -> Matrix3<f32> {
+        self.u.fixed_rows::<3>(0).into_owned()
+    }
+
+    /// Singular values.
+    pub fn s(&self) -> Vector3<f32> {
+        self.s.xyz()
+    }
+
+    /// Second orthogonal matrix of the SVD.
+    pub fn vt(&self) -> Matrix3<f32> {
+        self.vt.fixed_rows::<3>(0).into_owned()
+    }
+
+    /// Rebuilds the matrix this SVD is the decomposition of.
+    pub fn recompose(&self) -> Matrix3<f32> {
+        self.u() * Matrix3::from_diagonal(&self.s()) * self.vt()
+    }
+}
+
+impl From<SVD<f32, U3, U3>> for GpuSvd3 {
+    /// Converts a nalgebra SVD to its GPU representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `svd` was computed without `u` or `v_t`.
+    fn from(svd: SVD<f32, U3, U3>) -> Self {
+        Self::new(
+            svd.u.expect("GpuSvd3: the SVD must contain `u`."),
+            svd.singular_values,
+            svd.v_t.expect("GpuSvd3: the SVD must contain `v_t`."),
+        )
+    }
+}
+
+impl From<GpuSvd3> for SVD<f32, U3, U3> {
+    fn from(svd: GpuSvd3) -> Self {
+        SVD {
+            u: Some(svd.u()),
+            v_t: Some(svd.vt()),
+            singular_values: svd.s(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GpuSvd3;
+    use approx::assert_relative_eq;
+    use nalgebra::{Matrix3, SVD, U3};
+
+    #[test]
+    fn svd3_nalgebra_roundtrip() {
+        let m = Matrix3::<f32>::new_random().add_scalar(-0.5);
+        let svd = m.svd(true, true);
+        let gpu_svd = GpuSvd3::from(svd.clone());
+        let roundtrip: SVD<f32, U3, U3> = gpu_svd.into();
+
+        assert_eq!(roundtrip.u, svd.u);
+        assert_eq!(roundtrip.v_t, svd.v_t);
+        assert_eq!(roundtrip.singular_values, svd.singular_values);
+        assert_relative_eq!(gpu_svd.recompose(), m, epsilon = 1.0e-5);
+    }
+}
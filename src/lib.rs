@@ -18,6 +18,11 @@ pub mod tensor;
 pub const SLANG_SRC_DIR: include_dir::Dir<'_> =
     include_dir::include_dir!("$CARGO_MANIFEST_DIR/shaders");
 
+/// Directory of slang shaders only used by the tests, e.g., for user-defined operations.
+#[cfg(test)]
+pub(crate) const TEST_SLANG_SRC_DIR: include_dir::Dir<'_> =
+    include_dir::include_dir!("$CARGO_MANIFEST_DIR/tests/shaders");
+
 /// Register all the shaders from this crate (and its dependencies) as modules accessible to the
 /// `compiler`.
 ///
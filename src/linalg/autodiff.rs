@@ -0,0 +1,606 @@
+use crate::linalg::{
+    Contiguous, Fill, FusedElementwise, FusedExpr, FusedUnaryOp, Gemm, Gemv, OpAssign,
+    OpAssignVariant, Reduce, ReduceVariant,
+};
+use crate::shapes::ViewShapeBuffers;
+use crate::tensor::{GpuTensor, TensorBuilder};
+use slang_hal::backend::Backend;
+use slang_hal::{BufferUsages, Shader, SlangCompiler};
+
+/// A variable recorded on a [`Tape`].
+///
+/// This is a lightweight handle to a tensor owned by the tape. It is only meaningful for the
+/// tape that created it.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TapeVar(usize);
+
+#[derive(Copy, Clone, Debug)]
+enum TapeOp {
+    Gemm {
+        out: TapeVar,
+        a: TapeVar,
+        b: TapeVar,
+    },
+    Gemv {
+        out: TapeVar,
+        m: TapeVar,
+        v: TapeVar,
+    },
+    Binary {
+        out: TapeVar,
+        variant: OpAssignVariant,
+        a: TapeVar,
+        b: TapeVar,
+    },
+    Reduce {
+        out: TapeVar,
+        variant: ReduceVariant,
+        x: TapeVar,
+    },
+    Unary {
+        out: TapeVar,
+        op: FusedUnaryOp,
+        x: TapeVar,
+    },
+}
+
+impl TapeOp {
+    fn out(&self) -> TapeVar {
+        match *self {
+            Self::Gemm { out, .. }
+            | Self::Gemv { out, .. }
+            | Self::Binary { out, .. }
+            | Self::Reduce { out, .. }
+            | Self::Unary { out, .. } => out,
+        }
+    }
+
+    fn inputs(&self) -> [TapeVar; 2] {
+        match *self {
+            Self::Gemm { a, b, .. } | Self::Binary { a, b, .. } => [a, b],
+            Self::Gemv { m, v, .. } => [m, v],
+            Self::Reduce { x, .. } | Self::Unary { x, .. } => [x, x],
+        }
+    }
+}
+
+/// A tape recording [`Gemm`], [`Gemv`], [`OpAssign`], [`Reduce`], and unary-op dispatches for
+/// reverse-mode automatic differentiation.
+///
+/// Each recording method dispatches the forward kernel immediately and returns a [`TapeVar`]
+/// for its result. [`Tape::backward`] then dispatches the matching gradient kernels, in reverse
+/// order, and returns the gradients of the selected variables.
+///
+/// The tape owns the input tensors (registered with [`Tape::leaf`]), every intermediate result,
+/// and the temporary buffers of the backward pass. They are kept alive until the tape is
+/// [cleared](Tape::clear) or dropped so the tape must outlive the submission of the recorded
+/// passes.
+///
+/// The gradient kernels are generated with [`FusedElementwise`] and compiled at runtime, so the
+/// tape requires the `runtime` feature.
+pub struct Tape<B: Backend> {
+    gemm: Gemm<B>,
+    gemv: Gemv<B>,
+    op_assign: OpAssign<B>,
+    reduce: Reduce<B>,
+    fill: Fill<B>,
+    contiguous: Contiguous<B>,
+    fused: FusedElementwise<B>,
+    values: Vec<GpuTensor<f32, B>>,
+    ops: Vec<TapeOp>,
+    scratch: Vec<GpuTensor<f32, B>>,
+}
+
+impl<B: Backend> Tape<B> {
+    /// Loads the kernels needed by the tape and initializes an empty tape.
+    pub fn from_backend(backend: &B, compiler: &SlangCompiler) -> Result<Self, B::Error> {
+        Ok(Self {
+            gemm: Gemm::from_backend(backend, compiler)?,
+            gemv: Gemv::from_backend(backend, compiler)?,
+            op_assign: OpAssign::from_backend(backend, compiler)?,
+            reduce: Reduce::from_backend(backend, compiler)?,
+            fill: Fill::from_backend(backend, compiler)?,
+            contiguous: Contiguous::from_backend(backend, compiler)?,
+            fused: FusedElementwise::new(),
+            values: vec![],
+            ops: vec![],
+            scratch: vec![],
+        })
+    }
+
+    /// Removes every variable and operation recorded so far.
+    ///
+    /// The compiled kernels are preserved. Any [`TapeVar`] created before this call is
+    /// invalidated.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.ops.clear();
+        self.scratch.clear();
+    }
+
+    /// The number of operations recorded so far.
+    pub fn num_ops(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// The tensor holding the value of `var`.
+    ///
+    /// The value is only available once the passes recorded so far have been submitted.
+    pub fn value(&self, var: TapeVar) -> &GpuTensor<f32, B> {
+        &self.values[var.0]
+    }
+
+    /// Registers `tensor` as an input variable of the tape.
+    pub fn leaf(&mut self, tensor: GpuTensor<f32, B>) -> TapeVar {
+        self.values.push(tensor);
+        TapeVar(self.values.len() - 1)
+    }
+
+    fn push_uninit(&mut self, backend: &B, shape: [u32; 4]) -> Result<TapeVar, B::Error> {
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let tensor = TensorBuilder::tensor(shape, usage).build_uninit(backend)?;
+        Ok(self.leaf(tensor))
+    }
+
+    /// Records `a * b`, computed with [`Gemm::dispatch`].
+    ///
+    /// The backward pass relies on [`Gemm`] too, so every dimension of `a` and `b` must be a
+    /// multiple of 4.
+    pub fn matmul(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        a: TapeVar,
+        b: TapeVar,
+    ) -> Result<TapeVar, B::Error> {
+        let [nrows, _, nmats, _] = self.values[a.0].shape();
+        let ncols = self.values[b.0].size(1);
+        let out = self.push_uninit(backend, [nrows, ncols, nmats, 1])?;
+        self.gemm.dispatch(
+            backend,
+            shapes,
+            pass,
+            &self.values[out.0],
+            &self.values[a.0],
+            &self.values[b.0],
+        )?;
+        self.ops.push(TapeOp::Gemm { out, a, b });
+        Ok(out)
+    }
+
+    /// Records the matrix-vector product `m * v`, computed with [`Gemv::dispatch`].
+    pub fn gemv(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        m: TapeVar,
+        v: TapeVar,
+    ) -> Result<TapeVar, B::Error> {
+        let nrows = self.values[m.0].size(0);
+        let out = self.push_uninit(backend, [nrows, 1, 1, 1])?;
+        self.gemv.dispatch(
+            backend,
+            shapes,
+            pass,
+            &self.values[out.0],
+            &self.values[m.0],
+            &self.values[v.0],
+        )?;
+        self.ops.push(TapeOp::Gemv { out, m, v });
+        Ok(out)
+    }
+
+    /// Records the element-wise operation `a <variant> b`, computed with [`OpAssign::launch`].
+    ///
+    /// Only [`OpAssignVariant::Add`], [`OpAssignVariant::Sub`], [`OpAssignVariant::Mul`], and
+    /// [`OpAssignVariant::Div`] are differentiable. `a` and `b` must have the same shape.
+    pub fn binary(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: OpAssignVariant,
+        a: TapeVar,
+        b: TapeVar,
+    ) -> Result<TapeVar, B::Error> {
+        assert!(
+            matches!(
+                variant,
+                OpAssignVariant::Add
+                    | OpAssignVariant::Sub
+                    | OpAssignVariant::Mul
+                    | OpAssignVariant::Div
+            ),
+            "Tape: {variant:?} is not differentiable."
+        );
+        let shape = self.values[a.0].shape();
+        assert_eq!(
+            shape,
+            self.values[b.0].shape(),
+            "Tape: the operands must have the same shape."
+        );
+
+        let out = self.push_uninit(backend, shape)?;
+        let [out_val, a_val, b_val] = [out, a, b].map(|var| &self.values[var.0]);
+        let op_assign = &self.op_assign;
+        op_assign.launch(backend, shapes, pass, OpAssignVariant::Copy, out_val, a_val)?;
+        op_assign.launch(backend, shapes, pass, variant, out_val, b_val)?;
+        self.ops.push(TapeOp::Binary { out, variant, a, b });
+        Ok(out)
+    }
+
+    /// Records the reduction of the vector `x` into a scalar, computed with [`Reduce::launch`].
+    ///
+    /// Only [`ReduceVariant::Sum`] and [`ReduceVariant::SqNorm`] are differentiable.
+    pub fn reduce(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: ReduceVariant,
+        x: TapeVar,
+    ) -> Result<TapeVar, B::Error> {
+        assert!(
+            matches!(variant, ReduceVariant::Sum | ReduceVariant::SqNorm),
+            "Tape: {variant:?} is not differentiable."
+        );
+        let out = self.push_uninit(backend, [1, 1, 1, 1])?;
+        self.reduce.launch(
+            backend,
+            shapes,
+            pass,
+            variant,
+            &self.values[out.0],
+            &self.values[x.0],
+        )?;
+        self.ops.push(TapeOp::Reduce { out, variant, x });
+        Ok(out)
+    }
+
+    /// Records the element-wise unary operation `op(x)`, computed with a fused kernel.
+    pub fn unary(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        op: FusedUnaryOp,
+        x: TapeVar,
+    ) -> Result<TapeVar, B::Error> {
+        let out = self.push_uninit(backend, self.values[x.0].shape())?;
+        let expr = FusedExpr::input(0).unary(op);
+        let inputs = [self.values[x.0].as_view()];
+        self.fused
+            .launch(backend, shapes, pass, &expr, &self.values[out.0], &inputs)?;
+        self.ops.push(TapeOp::Unary { out, op, x });
+        Ok(out)
+    }
+
+    /// Dispatches the kernels computing the gradient of `output` with respect to each variable
+    /// of `wrt`.
+    ///
+    /// If `output` isn’t a scalar, this computes the gradient of the sum of its elements. The
+    /// returned gradients have the same shapes as the variables of `wrt`, and are zero for
+    /// variables `output` doesn’t depend on. They can be read once `pass` is submitted.
+    pub fn backward(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        output: TapeVar,
+        wrt: &[TapeVar],
+    ) -> Result<Vec<GpuTensor<f32, B>>, B::Error> {
+        // Only compute the gradients of variables that `output` depends on, and that depend on
+        // `wrt`.
+        let mut reached = vec![false; self.values.len()];
+        reached[output.0] = true;
+        for op in self.ops.iter().rev() {
+            if reached[op.out().0] {
+                for var in op.inputs() {
+                    reached[var.0] = true;
+                }
+            }
+        }
+        let mut needed = vec![false; self.values.len()];
+        for var in wrt {
+            assert!(!needed[var.0], "Tape: {var:?} appears twice in `wrt`.");
+            needed[var.0] = true;
+        }
+        for op in &self.ops {
+            if reached[op.out().0] && op.inputs().iter().any(|var| needed[var.0]) {
+                needed[op.out().0] = true;
+            }
+        }
+        needed[output.0] = true;
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let mut grads = vec![];
+        for (value, needed) in self.values.iter().zip(needed) {
+            let grad = if needed {
+                let mut grad = TensorBuilder::tensor(value.shape(), usage)
+                    .ordering(value.ordering())
+                    .build_uninit(backend)?;
+                self.fill.launch_zeros(backend, shapes, pass, &mut grad)?;
+                Some(grad)
+            } else {
+                None
+            };
+            grads.push(grad);
+        }
+        if let Some(grad) = &mut grads[output.0] {
+            self.fill.launch_ones(backend, shapes, pass, grad)?;
+        }
+
+        for i in (0..self.ops.len()).rev() {
+            let op = self.ops[i];
+            if grads[op.out().0].is_some() {
+                self.backward_op(backend, shapes, pass, op, &grads)?;
+            }
+        }
+
+        let result = wrt
+            .iter()
+            .map(|var| grads[var.0].take().unwrap_or_else(|| unreachable!()))
+            .collect();
+        // The gradients of the intermediate variables are still used by the recorded pass.
+        self.scratch.extend(grads.into_iter().flatten());
+        Ok(result)
+    }
+
+    fn backward_op(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        op: TapeOp,
+        grads: &[Option<GpuTensor<f32, B>>],
+    ) -> Result<(), B::Error> {
+        let value = |var: TapeVar| self.values[var.0].as_view();
+        let g = grads[op.out().0].as_ref().unwrap_or_else(|| unreachable!());
+        let [in0, in1, in2, in3] = [0, 1, 2, 3].map(FusedExpr::input);
+        let usage = BufferUsages::STORAGE;
+
+        match op {
+            TapeOp::Gemm { a, b, .. } => {
+                if let Some(ga) = &grads[a.0] {
+                    // ga += g * tr(b). Gemm only reads contiguous matrices so tr(b) is copied.
+                    let [nrows, ncols, nmats, _] = self.values[b.0].shape();
+                    let bt = TensorBuilder::tensor([ncols, nrows, nmats, 1], usage)
+                        .build_uninit(backend)?;
+                    let tmp = TensorBuilder::tensor(ga.shape(), usage).build_uninit(backend)?;
+                    self.contiguous
+                        .launch(backend, shapes, pass, &bt, value(b).transposed())?;
+                    self.gemm.dispatch(backend, shapes, pass, &tmp, g, &bt)?;
+                    self.op_assign
+                        .launch(backend, shapes, pass, OpAssignVariant::Add, ga, &tmp)?;
+                    self.scratch.extend([bt, tmp]);
+                }
+                if let Some(gb) = &grads[b.0] {
+                    // gb += tr(a) * g
+                    let tmp = TensorBuilder::tensor(gb.shape(), usage).build_uninit(backend)?;
+                    self.gemm
+                        .dispatch_tr(backend, shapes, pass, &tmp, value(a), g)?;
+                    self.op_assign
+                        .launch(backend, shapes, pass, OpAssignVariant::Add, gb, &tmp)?;
+                    self.scratch.push(tmp);
+                }
+            }
+            TapeOp::Gemv { m, v, .. } => {
+                if let Some(gm) = &grads[m.0] {
+                    // gm += g * tr(v), with `g` repeated along the columns and `tr(v)` along
+                    // the rows.
+                    let inputs = [gm.as_view(), g.as_view(), value(v).transposed()];
+                    self.fused
+                        .launch(backend, shapes, pass, &(in0 + in1 * in2), gm, &inputs)?;
+                }
+                if let Some(gv) = &grads[v.0] {
+                    // gv += tr(m) * g
+                    let tmp = TensorBuilder::tensor(gv.shape(), usage).build_uninit(backend)?;
+                    self.gemv
+                        .dispatch_tr(backend, shapes, pass, &tmp, value(m), g)?;
+                    self.op_assign
+                        .launch(backend, shapes, pass, OpAssignVariant::Add, gv, &tmp)?;
+                    self.scratch.push(tmp);
+                }
+            }
+            TapeOp::Binary { out, variant, a, b } => {
+                let (expr_a, expr_b) = match variant {
+                    OpAssignVariant::Add => (in0.clone() + in1.clone(), in0 + in1),
+                    OpAssignVariant::Sub => (in0.clone() + in1.clone(), in0 - in1),
+                    // ga += g * b, gb += g * a
+                    OpAssignVariant::Mul => (in0.clone() + in1.clone() * in2, in0 + in1 * in3),
+                    // ga += g / b, gb -= g * (a / b) / b
+                    OpAssignVariant::Div => (
+                        in0.clone() + in1.clone() / in2.clone(),
+                        in0 - in1 * in3 / in2,
+                    ),
+                    _ => unreachable!(),
+                };
+                let [vb, va] = match variant {
+                    // The output `a / b` is reused to compute the gradient of `b`.
+                    OpAssignVariant::Div => [value(b), value(out)],
+                    _ => [value(b), value(a)],
+                };
+                if let Some(ga) = &grads[a.0] {
+                    let inputs = [ga.as_view(), g.as_view(), vb, va];
+                    self.fused
+                        .launch(backend, shapes, pass, &expr_a, ga, &inputs)?;
+                }
+                if let Some(gb) = &grads[b.0] {
+                    let inputs = [gb.as_view(), g.as_view(), vb, va];
+                    self.fused
+                        .launch(backend, shapes, pass, &expr_b, gb, &inputs)?;
+                }
+            }
+            TapeOp::Reduce { variant, x, .. } => {
+                // `x` has no gradient if it doesn’t depend on `wrt`.
+                let Some(gx) = &grads[x.0] else {
+                    return Ok(());
+                };
+                // The scalar `g` is repeated to match the shape of `x`.
+                let expr = match variant {
+                    ReduceVariant::Sum => in0 + in1,
+                    ReduceVariant::SqNorm => in0 + FusedExpr::constant(2.0) * in1 * in2,
+                    _ => unreachable!(),
+                };
+                let inputs = [gx.as_view(), g.as_view(), value(x)];
+                self.fused
+                    .launch(backend, shapes, pass, &expr, gx, &inputs)?;
+            }
+            TapeOp::Unary { out, op, x } => {
+                let Some(gx) = &grads[x.0] else {
+                    return Ok(());
+                };
+                // The derivative of `y = op(x)`, with `x = in2` and `y = in3`.
+                let one = FusedExpr::constant(1.0);
+                let derivative = match op {
+                    FusedUnaryOp::Neg => -one,
+                    FusedUnaryOp::Abs => in2.sign(),
+                    FusedUnaryOp::Exp => in3,
+                    FusedUnaryOp::Log => one / in2,
+                    FusedUnaryOp::Sqrt => FusedExpr::constant(0.5) / in3,
+                    FusedUnaryOp::Tanh => one - in3.clone() * in3,
+                    FusedUnaryOp::Sigmoid => in3.clone() * (one - in3),
+                    FusedUnaryOp::Relu => in2.sign().max(FusedExpr::constant(0.0)),
+                    // The derivative is zero almost everywhere.
+                    FusedUnaryOp::Sign => return Ok(()),
+                };
+                let expr = in0 + in1 * derivative;
+                let inputs = [gx.as_view(), g.as_view(), value(x), value(out)];
+                self.fused
+                    .launch(backend, shapes, pass, &expr, gx, &inputs)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Tape;
+    use crate::linalg::{FusedUnaryOp, OpAssignVariant, ReduceVariant};
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::BufferUsages;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_autodiff_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_autodiff_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_autodiff_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_autodiff_generic(backend).await;
+    }
+
+    async fn gpu_autodiff_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let mut tape = Tape::from_backend(&backend, &compiler).unwrap();
+
+        const M: usize = 8;
+        const K: usize = 12;
+        const N: usize = 4;
+
+        let ma = DMatrix::<f32>::new_random(M, K).add_scalar(-0.5);
+        let mb = DMatrix::<f32>::new_random(K, N).add_scalar(-0.5);
+        let vv = DVector::<f32>::new_random(N).add_scalar(-0.5);
+        let vc = DVector::<f32>::new_random(M).add_scalar(-0.5);
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let a = tape.leaf(GpuTensor::matrix(&backend, &ma, usage).unwrap());
+        let b = tape.leaf(GpuTensor::matrix(&backend, &mb, usage).unwrap());
+        let v = tape.leaf(GpuTensor::vector(&backend, &vv, usage).unwrap());
+        let c = tape.leaf(GpuTensor::vector(&backend, &vc, usage).unwrap());
+
+        // loss1 = |tanh(a * b * v) * c|², loss2 = sum(relu(a * b * v - c))
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        let ab = tape.matmul(&backend, &mut shapes, &mut pass, a, b).unwrap();
+        let y = tape.gemv(&backend, &mut shapes, &mut pass, ab, v).unwrap();
+        let z = tape
+            .unary(&backend, &mut shapes, &mut pass, FusedUnaryOp::Tanh, y)
+            .unwrap();
+        let w = tape
+            .binary(&backend, &mut shapes, &mut pass, OpAssignVariant::Mul, z, c)
+            .unwrap();
+        let loss1 = tape
+            .reduce(&backend, &mut shapes, &mut pass, ReduceVariant::SqNorm, w)
+            .unwrap();
+        let s = tape
+            .binary(&backend, &mut shapes, &mut pass, OpAssignVariant::Sub, y, c)
+            .unwrap();
+        let r = tape
+            .unary(&backend, &mut shapes, &mut pass, FusedUnaryOp::Relu, s)
+            .unwrap();
+        let loss2 = tape
+            .reduce(&backend, &mut shapes, &mut pass, ReduceVariant::Sum, r)
+            .unwrap();
+        let grads1 = tape
+            .backward(&backend, &mut shapes, &mut pass, loss1, &[a, b, v, c])
+            .unwrap();
+        let grads2 = tape
+            .backward(&backend, &mut shapes, &mut pass, loss2, &[v, c])
+            .unwrap();
+        // loss3 = sum(tanh(a * b * v)) doesn’t depend on `c`.
+        let loss3 = tape
+            .reduce(&backend, &mut shapes, &mut pass, ReduceVariant::Sum, z)
+            .unwrap();
+        let grads3 = tape
+            .backward(&backend, &mut shapes, &mut pass, loss3, &[c])
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+        backend.submit(encoder).unwrap();
+        assert_eq!(tape.num_ops(), 9);
+
+        // Gradients computed by hand.
+        let h = &ma * &mb;
+        let y_cpu = &h * &vv;
+        let z_cpu = y_cpu.map(|e| e.tanh());
+        let w_cpu = z_cpu.component_mul(&vc);
+        let gw = &w_cpu * 2.0;
+        let gc1 = gw.component_mul(&z_cpu);
+        let gy1 = gw
+            .component_mul(&vc)
+            .component_mul(&z_cpu.map(|e| 1.0 - e * e));
+        let gh1 = &gy1 * vv.transpose();
+        let gv1 = h.tr_mul(&gy1);
+        let ga1 = &gh1 * mb.transpose();
+        let gb1 = ma.tr_mul(&gh1);
+
+        let gy2 = (&y_cpu - &vc).map(|e| if e > 0.0 { 1.0 } else { 0.0 });
+        let gc2 = -&gy2;
+        let gv2 = h.tr_mul(&gy2);
+
+        let expected = [
+            (&grads1[0], ga1),
+            (&grads1[1], gb1),
+            (&grads1[2], DMatrix::from_column_slice(N, 1, gv1.as_slice())),
+            (&grads1[3], DMatrix::from_column_slice(M, 1, gc1.as_slice())),
+            (&grads2[0], DMatrix::from_column_slice(N, 1, gv2.as_slice())),
+            (&grads2[1], DMatrix::from_column_slice(M, 1, gc2.as_slice())),
+            (&grads3[0], DMatrix::zeros(M, 1)),
+        ];
+        for (grad, cpu_grad) in expected {
+            let mut gpu_grad = DMatrix::zeros(cpu_grad.nrows(), cpu_grad.ncols());
+            backend
+                .slow_read_buffer(grad.buffer(), gpu_grad.as_mut_slice())
+                .await
+                .unwrap();
+            assert_relative_eq!(gpu_grad, cpu_grad, epsilon = 1.0e-4);
+        }
+    }
+}
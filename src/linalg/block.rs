@@ -0,0 +1,232 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::{Backend, Buffer};
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// Slang module for copying sub-matrices to and from zero-padded blocks.
+///
+/// This is mostly useful for running kernels with strict layout requirements, like [`Gemm`]
+/// (which needs contiguous matrices with dimensions multiple of 4), on arbitrary sub-matrices:
+/// the sub-matrices are packed into properly aligned blocks before the operation, and its
+/// result is unpacked afterward.
+///
+/// Unlike most kernels, the sub-matrix views aren’t bound with their offset, so they can start
+/// at any element of their buffer regardless of the storage buffer offset alignment required by
+/// the backend.
+///
+/// [`Gemm`]: crate::linalg::Gemm
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::block")]
+pub struct Block<B: Backend> {
+    /// Kernel copying a sub-matrix into a zero-padded block.
+    pub pack: GpuFunction<B>,
+    /// Kernel copying the top-left corner of a block into a sub-matrix.
+    pub unpack: GpuFunction<B>,
+    /// Kernel subtracting the top-left corner of a block from a sub-matrix.
+    pub unpack_sub: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct BlockParams {
+    offset_a: u32,
+    padding: [u32; 3],
+}
+
+#[derive(ShaderArgs)]
+struct BlockArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_a: &'a B::Buffer<ViewShape>,
+    shape_block: &'a B::Buffer<ViewShape>,
+    a: B::BufferSlice<'a, f32>,
+    block: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> Block<B> {
+    /// Launches the kernel copying `a` into the top-left corner of `block`, and setting the rest
+    /// of `block` to zero.
+    ///
+    /// The `block` must be at least as large as `a` along every dimension.
+    pub fn launch_pack<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        block: impl Into<GpuTensorView<'a, f32, B>>,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let block = block.into();
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            &self.pack,
+            a.into(),
+            block,
+            block.len(),
+        )
+    }
+
+    /// Launches the kernel copying the top-left corner of `block` into `a`.
+    ///
+    /// The `block` must be at least as large as `a` along every dimension.
+    pub fn launch_unpack<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        block: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let a = a.into();
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            &self.unpack,
+            a,
+            block.into(),
+            a.len(),
+        )
+    }
+
+    /// Launches the kernel subtracting the top-left corner of `block` from `a`.
+    ///
+    /// The `block` must be at least as large as `a` along every dimension.
+    pub fn launch_unpack_sub<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        block: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let a = a.into();
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            &self.unpack_sub,
+            a,
+            block.into(),
+            a.len(),
+        )
+    }
+
+    fn launch_generic(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        a: GpuTensorView<f32, B>,
+        block: GpuTensorView<f32, B>,
+        num_threads: u64,
+    ) -> Result<(), B::Error> {
+        let shape_a = a.shape();
+        let shape_block = block.shape();
+        assert!(
+            (0..4).all(|k| shape_a.size[k] <= shape_block.size[k]),
+            "Block: the block must be at least as large as the sub-matrix."
+        );
+
+        if num_threads == 0 {
+            return Ok(());
+        }
+
+        let params = BlockParams {
+            offset_a: a.offset(),
+            padding: [0; 3],
+        };
+        shapes.insert(backend, shape_a)?;
+        shapes.insert(backend, shape_block)?;
+        shapes.insert_params(backend, params)?;
+        let args = BlockArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape_a: shapes.get(shape_a).unwrap_or_else(|| unreachable!()),
+            shape_block: shapes.get(shape_block).unwrap_or_else(|| unreachable!()),
+            a: a.raw_buffer().slice(0..),
+            block: block.buffer(),
+        };
+        function.launch_capped(backend, pass, &args, num_threads as u32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_block_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_block_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_block_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_block_generic(backend).await;
+    }
+
+    async fn gpu_block_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let block = super::Block::from_backend(&backend, &compiler).unwrap();
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let a = DMatrix::<f32>::new_random(37, 29);
+        let b = DMatrix::<f32>::new_random(12, 8);
+        let gpu_a = GpuTensor::matrix(&backend, &a, usage).unwrap();
+        let gpu_packed = GpuTensor::matrix(&backend, &DMatrix::<f32>::zeros(12, 8), usage).unwrap();
+        let gpu_b = GpuTensor::matrix(&backend, &b, usage).unwrap();
+
+        // A sub-matrix with an offset that isn’t a multiple of the storage buffer alignment.
+        let (row, col) = (5, 3);
+        let sub_a = gpu_a.as_view().narrow(0, row, 9).narrow(1, col, 7);
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        block
+            .launch_pack(&backend, &mut shapes, &mut pass, &gpu_packed, sub_a)
+            .unwrap();
+        block
+            .launch_unpack_sub(&backend, &mut shapes, &mut pass, sub_a, &gpu_b)
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+        backend.submit(encoder).unwrap();
+
+        let mut packed = DMatrix::zeros(12, 8);
+        let mut result = DMatrix::zeros(37, 29);
+        backend
+            .slow_read_buffer(gpu_packed.buffer(), packed.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_a.buffer(), result.as_mut_slice())
+            .await
+            .unwrap();
+
+        let mut expected_packed = DMatrix::zeros(12, 8);
+        expected_packed
+            .view_mut((0, 0), (9, 7))
+            .copy_from(&a.view((row as usize, col as usize), (9, 7)));
+        assert_eq!(packed, expected_packed);
+
+        let mut expected = a.clone();
+        expected
+            .view_mut((row as usize, col as usize), (9, 7))
+            .zip_apply(&b.view((0, 0), (9, 7)), |x, y| *x -= y);
+        assert_eq!(result, expected);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::linalg::{Scan, ScanVariant};
+use crate::shapes::{MatrixOrdering, ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use bytemuck::NoUninit;
+use slang_hal::backend::{Backend, DeviceValue};
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+// Alignment (in number of elements) of each part of the workspace. This matches the most
+// restrictive storage buffer offset alignment (256 bytes) for 4-bytes elements.
+const WORKSPACE_ALIGN: u32 = 64;
+
+/// Value types supported by the [`Compact`] kernel.
+///
+/// This is implemented for `f32` and `u32`.
+pub trait CompactValue: DeviceValue + NoUninit {
+    #[doc(hidden)]
+    fn compact_function<B: Backend>(compact: &Compact<B>) -> &GpuFunction<B>;
+}
+
+impl CompactValue for f32 {
+    fn compact_function<B: Backend>(compact: &Compact<B>) -> &GpuFunction<B> {
+        &compact.compact_f32
+    }
+}
+
+impl CompactValue for u32 {
+    fn compact_function<B: Backend>(compact: &Compact<B>) -> &GpuFunction<B> {
+        &compact.compact_u32
+    }
+}
+
+/// Slang module for stream compaction: keeping only the elements of a tensor selected by a mask.
+///
+/// The selected elements are written contiguously to the output, in the order of their linear
+/// index, and their number is written to a one-element tensor. This doesn’t require reading
+/// anything back on the CPU.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::compact")]
+pub struct Compact<B: Backend> {
+    /// Kernel converting the mask to `0` or `1` flags.
+    pub flags: GpuFunction<B>,
+    /// Kernel moving the selected `f32` values to the output.
+    pub compact_f32: GpuFunction<B>,
+    /// Kernel moving the selected `u32` values to the output.
+    pub compact_u32: GpuFunction<B>,
+    /// Kernel writing the linear indices of the selected elements to the output.
+    pub compact_indices: GpuFunction<B>,
+}
+
+#[derive(ShaderArgs)]
+struct CompactArgs<'a, T: DeviceValue, B: Backend> {
+    shape_mask: Option<&'a B::Buffer<ViewShape>>,
+    shape_values: Option<&'a B::Buffer<ViewShape>>,
+    mask: Option<B::BufferSlice<'a, u32>>,
+    flags: B::BufferSlice<'a, u32>,
+    positions: Option<B::BufferSlice<'a, u32>>,
+    values: Option<B::BufferSlice<'a, T>>,
+    out: Option<B::BufferSlice<'a, T>>,
+    count: Option<B::BufferSlice<'a, u32>>,
+}
+
+impl<B: Backend> Compact<B> {
+    /// The minimum number of elements of the workspace needed for compacting a tensor with the
+    /// given `shape`.
+    pub fn workspace_len(&self, scan: &Scan<B>, shape: ViewShape) -> u32 {
+        let len = shape.len() as u32;
+        let aligned_len = len.next_multiple_of(WORKSPACE_ALIGN);
+        aligned_len * 2 + scan.workspace_len(Self::flags_shape(len), 0)
+    }
+
+    /// Launches the kernels copying to `out` every element of `values` for which `mask` is
+    /// non-zero, and writing the number of such elements to `count`.
+    ///
+    /// The `mask` must have the same shape as `values`. The elements are compacted in the order
+    /// of their linear index. `out` must have at least as many elements as `values`; its elements
+    /// past `count` are left untouched. The `workspace` must be contiguous and have at least
+    /// [`Self::workspace_len`] elements.
+    pub fn launch<'a, T: CompactValue>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        scan: &Scan<B>,
+        out: impl Into<GpuTensorView<'a, T, B>>,
+        count: impl Into<GpuTensorView<'a, u32, B>>,
+        values: impl Into<GpuTensorView<'a, T, B>>,
+        mask: impl Into<GpuTensorView<'a, u32, B>>,
+        workspace: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        let values = values.into();
+        let mask = mask.into();
+        assert_eq!(
+            values.shape().size,
+            mask.shape().size,
+            "Compact: the values and mask shapes must be equal."
+        );
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            scan,
+            T::compact_function(self),
+            out.into(),
+            count.into(),
+            Some(values),
+            mask,
+            workspace.into(),
+        )
+    }
+
+    /// Launches the kernels writing to `out` the linear index of every element for which `mask`
+    /// is non-zero, and writing the number of such elements to `count`.
+    ///
+    /// See [`Self::launch`] for details on the other arguments.
+    pub fn launch_indices<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        scan: &Scan<B>,
+        out: impl Into<GpuTensorView<'a, u32, B>>,
+        count: impl Into<GpuTensorView<'a, u32, B>>,
+        mask: impl Into<GpuTensorView<'a, u32, B>>,
+        workspace: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            scan,
+            &self.compact_indices,
+            out.into(),
+            count.into(),
+            None,
+            mask.into(),
+            workspace.into(),
+        )
+    }
+
+    fn flags_shape(len: u32) -> ViewShape {
+        ViewShape::contiguous([len, 1, 1, 1], MatrixOrdering::ColumnMajor)
+    }
+
+    fn launch_generic<T: DeviceValue>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        scan: &Scan<B>,
+        compact: &GpuFunction<B>,
+        out: GpuTensorView<T, B>,
+        count: GpuTensorView<u32, B>,
+        values: Option<GpuTensorView<T, B>>,
+        mask: GpuTensorView<u32, B>,
+        workspace: GpuTensorView<u32, B>,
+    ) -> Result<(), B::Error> {
+        let len = mask.len() as u32;
+        assert!(
+            out.len() >= len as u64,
+            "Compact: the output must have at least {len} elements."
+        );
+        assert!(
+            out.is_contiguous().is_some(),
+            "Compact: the output must be contiguous."
+        );
+        assert_eq!(count.len(), 1, "Compact: the count must have one element.");
+        assert!(
+            workspace.len() >= self.workspace_len(scan, mask.shape()) as u64,
+            "Compact: the workspace is too small."
+        );
+        assert!(
+            workspace.is_contiguous().is_some(),
+            "Compact: the workspace must be contiguous."
+        );
+
+        let aligned_len = len.next_multiple_of(WORKSPACE_ALIGN);
+        let scan_len = workspace.len() as u32 - aligned_len * 2;
+        let flags = workspace.view(0, [len], [None]);
+        let positions = workspace.view(aligned_len, [len], [None]);
+        let scan_workspace = workspace.view(aligned_len * 2, [scan_len], [None]);
+
+        let shape_mask = mask.shape();
+        let shape_values = values.as_ref().map(|values| values.shape());
+        shapes.insert(backend, shape_mask)?;
+        if let Some(shape_values) = shape_values {
+            shapes.insert(backend, shape_values)?;
+        }
+
+        if len != 0 {
+            let flags_args = CompactArgs::<u32, B> {
+                shape_mask: Some(shapes.get(shape_mask).unwrap_or_else(|| unreachable!())),
+                shape_values: None,
+                mask: Some(mask.buffer()),
+                flags: flags.buffer(),
+                positions: None,
+                values: None,
+                out: None,
+                count: None,
+            };
+            self.flags.launch_capped(backend, pass, &flags_args, len)?;
+
+            scan.launch(
+                backend,
+                shapes,
+                pass,
+                ScanVariant::Sum,
+                true,
+                0,
+                positions,
+                flags,
+                scan_workspace,
+            )?;
+        }
+
+        let shape_values =
+            shape_values.map(|shape| shapes.get(shape).unwrap_or_else(|| unreachable!()));
+        let compact_args = CompactArgs {
+            shape_mask: Some(shapes.get(shape_mask).unwrap_or_else(|| unreachable!())),
+            shape_values,
+            mask: None,
+            flags: flags.buffer(),
+            positions: Some(positions.buffer()),
+            values: values.as_ref().map(|values| values.buffer()),
+            out: Some(out.buffer()),
+            count: Some(count.buffer()),
+        };
+        // NOTE: launch at least one thread so the count is written even if the mask is empty.
+        compact.launch_capped(backend, pass, &compact_args, len.max(1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::linalg::Scan;
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_compact_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_compact_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_compact_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_compact_generic(backend).await;
+    }
+
+    async fn gpu_compact_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let compact = super::Compact::from_backend(&backend, &compiler).unwrap();
+        let scan = Scan::from_backend(&backend, &compiler).unwrap();
+
+        // NOTE: the matrix is large enough to need several levels of scans.
+        const NROWS: usize = 301;
+        const NCOLS: usize = 613;
+        const LEN: usize = NROWS * NCOLS;
+
+        let values = DMatrix::<f32>::new_random(NROWS, NCOLS);
+        let mask = values.map(|x| (x > 0.7) as u32);
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let gpu_values = GpuTensor::matrix(&backend, &values, usage).unwrap();
+        let gpu_mask = GpuTensor::matrix(&backend, &mask, usage).unwrap();
+        let gpu_out = GpuTensor::vector(&backend, vec![0.0f32; LEN], usage).unwrap();
+        let gpu_indices = GpuTensor::vector(&backend, vec![0u32; LEN], usage).unwrap();
+        let gpu_count = GpuTensor::vector(&backend, [u32::MAX], usage).unwrap();
+        let gpu_indices_count = GpuTensor::vector(&backend, [u32::MAX], usage).unwrap();
+        let workspace_len = compact.workspace_len(&scan, gpu_mask.as_view().shape());
+        let gpu_workspace =
+            GpuTensor::vector(&backend, vec![0u32; workspace_len as usize], usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        compact
+            .launch(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &scan,
+                &gpu_out,
+                &gpu_count,
+                &gpu_values,
+                &gpu_mask,
+                &gpu_workspace,
+            )
+            .unwrap();
+        compact
+            .launch_indices(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &scan,
+                &gpu_indices,
+                &gpu_indices_count,
+                &gpu_mask,
+                &gpu_workspace,
+            )
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+
+        let mut out = vec![0.0f32; LEN];
+        let mut indices = vec![0u32; LEN];
+        let mut count = [0u32];
+        let mut indices_count = [0u32];
+        backend
+            .slow_read_buffer(gpu_out.buffer(), &mut out)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_indices.buffer(), &mut indices)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_count.buffer(), &mut count)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_indices_count.buffer(), &mut indices_count)
+            .await
+            .unwrap();
+
+        let cpu_indices: Vec<u32> = (0..LEN as u32).filter(|i| mask[*i as usize] != 0).collect();
+        let cpu_out: Vec<f32> = cpu_indices.iter().map(|i| values[*i as usize]).collect();
+        let cpu_count = cpu_indices.len();
+
+        assert_eq!(count[0] as usize, cpu_count);
+        assert_eq!(indices_count[0] as usize, cpu_count);
+        assert_eq!(&out[..cpu_count], &cpu_out[..]);
+        assert_eq!(&indices[..cpu_count], &cpu_indices[..]);
+    }
+}
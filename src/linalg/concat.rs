@@ -0,0 +1,318 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::{Backend, Buffer};
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// The maximum number of input tensors a single dispatch of the [`Concat`] kernel can read from.
+///
+/// Concatenations involving more inputs are split into several dispatches.
+pub const CONCAT_MAX_INPUTS: usize = 4;
+
+/// Slang module for concatenating or stacking tensors along an axis.
+///
+/// Splitting a tensor doesn’t require any kernel since it can be expressed as a set of views.
+/// See [`GpuTensorView::split`] and [`GpuTensorView::chunk`].
+///
+/// The tensors are bound from the start of their buffers, and their offsets are passed to the
+/// kernel, so views can start at any element regardless of the storage buffer offset alignment
+/// required by the backend.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::concat")]
+pub struct Concat<B: Backend> {
+    /// Kernel for copying up to [`CONCAT_MAX_INPUTS`] (possibly non-contiguous) tensors into
+    /// consecutive slots of an output tensor.
+    pub concat: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ConcatParams {
+    ends: [u32; 4],
+    offsets: [u32; 4],
+    input_offsets: [u32; 4],
+}
+
+#[derive(ShaderArgs)]
+struct ConcatArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_out: &'a B::Buffer<ViewShape>,
+    shape_in0: &'a B::Buffer<ViewShape>,
+    shape_in1: &'a B::Buffer<ViewShape>,
+    shape_in2: &'a B::Buffer<ViewShape>,
+    shape_in3: &'a B::Buffer<ViewShape>,
+    out: B::BufferSlice<'a, f32>,
+    input0: B::BufferSlice<'a, f32>,
+    input1: B::BufferSlice<'a, f32>,
+    input2: B::BufferSlice<'a, f32>,
+    input3: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> Concat<B> {
+    /// Launches the kernel for concatenating all the `inputs` along the dimension `axis` and
+    /// writing the result into `out`.
+    ///
+    /// All the inputs must have the same size as `out` along every dimension except `axis`. The
+    /// sizes of the inputs along `axis` must add up to the size of `out` along `axis`. The inputs
+    /// don’t need to be contiguous.
+    ///
+    /// Up to [`CONCAT_MAX_INPUTS`] inputs are handled by a single dispatch.
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        inputs: &[GpuTensorView<'a, f32, B>],
+    ) -> Result<(), B::Error> {
+        let inputs: Vec<_> = inputs.iter().map(|input| (input.shape(), *input)).collect();
+        self.launch_with_shapes(backend, shapes, pass, axis, out.into(), &inputs)
+    }
+
+    /// Launches the kernel for stacking all the `inputs` along a new dimension `axis` and writing
+    /// the result into `out`.
+    ///
+    /// The dimensions of the inputs starting at `axis` are shifted by one to make room for the new
+    /// dimension, so the last dimension of each input must have a size of 1. For example, stacking
+    /// `k` matrices of size `[nrows, ncols, 1, 1]` along the axis 2 results in a tensor of size
+    /// `[nrows, ncols, k, 1]`.
+    pub fn launch_stack<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        inputs: &[GpuTensorView<'a, f32, B>],
+    ) -> Result<(), B::Error> {
+        assert!(axis < 4, "Invalid stacking axis {axis}.");
+        let inputs: Vec<_> = inputs
+            .iter()
+            .map(|input| (insert_unit_dim(input.shape(), axis), *input))
+            .collect();
+        self.launch_with_shapes(backend, shapes, pass, axis, out.into(), &inputs)
+    }
+
+    fn launch_with_shapes<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: GpuTensorView<'a, f32, B>,
+        inputs: &[(ViewShape, GpuTensorView<'a, f32, B>)],
+    ) -> Result<(), B::Error> {
+        assert!(axis < 4, "Invalid concatenation axis {axis}.");
+        let shape_out = out.shape();
+
+        let mut axis_len = 0;
+        for (shape_in, _) in inputs {
+            for k in (0..4).filter(|k| *k != axis) {
+                assert_eq!(
+                    shape_in.size[k], shape_out.size[k],
+                    "Concat: dimension mismatch."
+                );
+            }
+            axis_len += shape_in.size[axis];
+        }
+        assert_eq!(
+            axis_len, shape_out.size[axis],
+            "Concat: the inputs sizes along the axis {axis} don’t add up to the output size."
+        );
+
+        let mut start = 0;
+        for batch in inputs.chunks(CONCAT_MAX_INPUTS) {
+            let mut params = ConcatParams {
+                ends: [0; 4],
+                offsets: [0; 4],
+                input_offsets: [0; 4],
+            };
+            let mut end = 0;
+
+            for k in 0..CONCAT_MAX_INPUTS {
+                if let Some((shape_in, input)) = batch.get(k) {
+                    end += shape_in.len() as u32;
+                    params.offsets[k] = out.offset() + start * shape_out.stride[axis];
+                    params.input_offsets[k] = input.offset();
+                    start += shape_in.size[axis];
+                }
+                params.ends[k] = end;
+            }
+
+            if end == 0 {
+                continue;
+            }
+
+            // Unused slots are bound to the first input. They are never read from since their
+            // range is empty.
+            let slot = |k: usize| batch.get(k).unwrap_or(&batch[0]);
+
+            shapes.insert(backend, shape_out)?;
+            for k in 0..CONCAT_MAX_INPUTS {
+                shapes.insert(backend, slot(k).0)?;
+            }
+            shapes.insert_params(backend, params)?;
+
+            let shape = |k: usize| shapes.get(slot(k).0).unwrap_or_else(|| unreachable!());
+            let args = ConcatArgs {
+                params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+                shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+                shape_in0: shape(0),
+                shape_in1: shape(1),
+                shape_in2: shape(2),
+                shape_in3: shape(3),
+                out: out.raw_buffer().slice(0..),
+                input0: slot(0).1.raw_buffer().slice(0..),
+                input1: slot(1).1.raw_buffer().slice(0..),
+                input2: slot(2).1.raw_buffer().slice(0..),
+                input3: slot(3).1.raw_buffer().slice(0..),
+            };
+            self.concat.launch_capped(backend, pass, &args, end)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Inserts a dimension of size 1 at `axis`, shifting all the subsequent dimensions.
+fn insert_unit_dim(shape: ViewShape, axis: usize) -> ViewShape {
+    assert_eq!(
+        shape.size[3], 1,
+        "Stack: the last dimension of the inputs must have a size of 1."
+    );
+    let mut result = shape;
+    for k in (axis + 1..4).rev() {
+        result.size[k] = shape.size[k - 1];
+        result.stride[k] = shape.stride[k - 1];
+    }
+    result.size[axis] = 1;
+    result.stride[axis] = 0;
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_concat_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_concat_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_concat_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_concat_generic(backend).await;
+    }
+
+    async fn gpu_concat_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let concat = super::Concat::from_backend(&backend, &compiler).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+
+        const NROWS: usize = 37;
+        // More inputs than `CONCAT_MAX_INPUTS` to check multi-dispatch concatenations.
+        const NCOLS: [u32; 5] = [3, 5, 1, 8, 2];
+        const NVECS: usize = 3;
+
+        let mats: Vec<_> = NCOLS
+            .iter()
+            .map(|ncols| DMatrix::<f32>::new_random(NROWS, *ncols as usize))
+            .collect();
+        let vecs: Vec<_> = (0..NVECS)
+            .map(|_| DVector::<f32>::new_random(NROWS))
+            .collect();
+
+        // The second matrix is stored transposed to check non-contiguous inputs.
+        let gpu_mats: Vec<_> = mats
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                let m = if i == 1 { m.transpose() } else { m.clone() };
+                GpuTensor::matrix(&backend, &m, BufferUsages::STORAGE).unwrap()
+            })
+            .collect();
+        let views: Vec<_> = gpu_mats
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if i == 1 {
+                    m.as_view().transposed()
+                } else {
+                    m.as_view()
+                }
+            })
+            .collect();
+        let gpu_vecs: Vec<_> = vecs
+            .iter()
+            .map(|v| GpuTensor::vector(&backend, v, BufferUsages::STORAGE).unwrap())
+            .collect();
+        let vec_views: Vec<_> = gpu_vecs.iter().map(|v| v.as_view()).collect();
+
+        let total_cols = NCOLS.iter().sum::<u32>() as usize;
+        let mut gpu_concat = DMatrix::<f32>::zeros(NROWS, total_cols);
+        let mut gpu_roundtrip = DMatrix::<f32>::zeros(NROWS, total_cols);
+        let mut gpu_stack = DMatrix::<f32>::zeros(NROWS, NVECS);
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let concatenated = GpuTensor::matrix(&backend, &gpu_concat, usage).unwrap();
+        let roundtrip = GpuTensor::matrix(&backend, &gpu_roundtrip, usage).unwrap();
+        let stacked = GpuTensor::matrix(&backend, &gpu_stack, usage).unwrap();
+
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        concat
+            .launch(&backend, &mut shapes, &mut pass, 1, &concatenated, &views)
+            .unwrap();
+        // Splitting then concatenating again must give back the same matrix. The split views
+        // start at offsets (e.g. `3 * NROWS`) that aren’t aligned to the storage buffer alignment.
+        let split = concatenated.split(1, &NCOLS);
+        concat
+            .launch(&backend, &mut shapes, &mut pass, 1, &roundtrip, &split)
+            .unwrap();
+        concat
+            .launch_stack(&backend, &mut shapes, &mut pass, 1, &stacked, &vec_views)
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+        backend
+            .slow_read_buffer(concatenated.buffer(), gpu_concat.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(roundtrip.buffer(), gpu_roundtrip.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(stacked.buffer(), gpu_stack.as_mut_slice())
+            .await
+            .unwrap();
+
+        let mut cpu_concat = DMatrix::zeros(NROWS, total_cols);
+        let mut first_col = 0;
+        for m in &mats {
+            cpu_concat.columns_mut(first_col, m.ncols()).copy_from(m);
+            first_col += m.ncols();
+        }
+        let cpu_stack = DMatrix::from_columns(&vecs);
+
+        // NOTE: don't use assert_relative_eq so it doesn't print out the whole matrices
+        //       when it fails (it tends to break rustrover tests integration).
+        assert!(gpu_concat == cpu_concat);
+        assert!(gpu_roundtrip == cpu_concat);
+        assert!(gpu_stack == cpu_stack);
+    }
+}
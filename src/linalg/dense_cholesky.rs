@@ -0,0 +1,419 @@
+use crate::linalg::{Block, Gemm};
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+// Alignment (in number of elements) of each part of the workspace. This matches the most
+// restrictive storage buffer offset alignment (256 bytes) for 4-bytes elements.
+const WORKSPACE_ALIGN: u32 = 64;
+// The number of columns factored by each panel of the blocked factorization.
+const BLOCK_SIZE: u32 = 64;
+
+/// Slang module for the Cholesky factorization of a single large symmetric positive-definite
+/// matrix.
+///
+/// The factorization is blocked and right-looking: each panel of 64 columns is factored by a
+/// single workgroup, and the trailing sub-matrix is then updated with [`Gemm`]. This is the
+/// dense counterpart of the batched small-matrix [`Cholesky`](crate::geometry::Cholesky).
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::dense_cholesky")]
+pub struct DenseCholesky<B: Backend> {
+    /// Kernel factoring one panel of the matrix.
+    pub chol_panel: GpuFunction<B>,
+    /// Kernel setting the strictly upper-triangular part of the factor to zero.
+    pub chol_zero_upper: GpuFunction<B>,
+    /// Kernel solving a diagonal block with the lower-triangular factor.
+    pub chol_solve_lower_diag: GpuFunction<B>,
+    /// Kernel updating the rows below a diagonal block solved with the lower-triangular factor.
+    pub chol_solve_lower_update: GpuFunction<B>,
+    /// Kernel solving a diagonal block with the transposed factor.
+    pub chol_solve_upper_diag: GpuFunction<B>,
+    /// Kernel updating the rows above a diagonal block solved with the transposed factor.
+    pub chol_solve_upper_update: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct DenseCholeskyParams {
+    start: u32,
+    len: u32,
+    padding: [u32; 2],
+}
+
+#[derive(ShaderArgs)]
+struct DenseCholeskyArgs<'a, B: Backend> {
+    params: Option<&'a B::Buffer<u32>>,
+    shape_l: &'a B::Buffer<ViewShape>,
+    shape_status: Option<&'a B::Buffer<ViewShape>>,
+    shape_b: Option<&'a B::Buffer<ViewShape>>,
+    l: B::BufferSlice<'a, f32>,
+    status: Option<B::BufferSlice<'a, u32>>,
+    b: Option<B::BufferSlice<'a, f32>>,
+}
+
+/// Checks that `l` is a single square matrix and returns its dimension.
+fn check_shape(l: ViewShape) -> u32 {
+    let [n, ncols, nmats, ncubes] = l.size;
+    assert_eq!(n, ncols, "DenseCholesky: the matrix must be square.");
+    assert_eq!(
+        (nmats, ncubes),
+        (1, 1),
+        "DenseCholesky: only one matrix is supported."
+    );
+    n
+}
+
+impl<B: Backend> DenseCholesky<B> {
+    /// The minimum number of elements of the workspace needed by [`Self::potrf`] for
+    /// factoring a `n x n` matrix.
+    pub fn workspace_len(&self, n: u32) -> u32 {
+        let m_pad = n.saturating_sub(BLOCK_SIZE).next_multiple_of(4);
+        (m_pad * BLOCK_SIZE).next_multiple_of(WORKSPACE_ALIGN) + m_pad * m_pad
+    }
+
+    /// Computes, in-place, the Cholesky factorization `A = L * transpose(L)` of the symmetric
+    /// positive-definite matrix `l`.
+    ///
+    /// Only the lower-triangular part of `A` is read. After this call, `l` contains the
+    /// lower-triangular factor `L`, and its strictly upper-triangular part is set to zero.
+    ///
+    /// A single value is written to `status`, of shape `[1, 1, 1, 1]`. It is zero if the
+    /// factorization succeeded. Otherwise, the matrix isn’t positive-definite (or contains NaNs),
+    /// and `status - 1` is the index of the column where the factorization stopped. In that
+    /// case, only the columns of `l` before that index contain the factor: the next columns are
+    /// still modified by the trailing updates, so their content is unspecified. The `workspace`
+    /// must be contiguous and have at least [`Self::workspace_len`] elements.
+    #[allow(clippy::too_many_arguments)]
+    pub fn potrf<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        gemm: &Gemm<B>,
+        block: &Block<B>,
+        l: impl Into<GpuTensorView<'a, f32, B>>,
+        status: impl Into<GpuTensorView<'a, u32, B>>,
+        workspace: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let l = l.into();
+        let status = status.into();
+        let workspace = workspace.into();
+        let n = check_shape(l.shape());
+        assert_eq!(
+            status.shape().size,
+            [1, 1, 1, 1],
+            "DenseCholesky: the status must have one element."
+        );
+        assert!(
+            workspace.len() >= self.workspace_len(n) as u64,
+            "DenseCholesky: the workspace is too small."
+        );
+        assert!(
+            workspace.is_contiguous().is_some(),
+            "DenseCholesky: the workspace must be contiguous."
+        );
+
+        for start in (0..n).step_by(BLOCK_SIZE as usize) {
+            let len = BLOCK_SIZE.min(n - start);
+            let end = start + len;
+            let params = DenseCholeskyParams {
+                start,
+                len,
+                padding: [0; 2],
+            };
+            self.launch_kernel(
+                backend,
+                shapes,
+                pass,
+                &self.chol_panel,
+                Some(params),
+                l,
+                Some(status),
+                None,
+                None,
+            )?;
+
+            if end == n {
+                continue;
+            }
+
+            // Update A22 -= L21 * tr(L21). `L21` is packed, transposed, into a zero-padded block
+            // matching the alignment requirements of `Gemm`.
+            let m = n - end;
+            let m_pad = m.next_multiple_of(4);
+            let part_len = (m_pad * BLOCK_SIZE).next_multiple_of(WORKSPACE_ALIGN);
+            let l21_tr = workspace.view(0, [BLOCK_SIZE, m_pad], [None, None]);
+            let prod = workspace.view(part_len, [m_pad, m_pad], [None, None]);
+            let l21 = l.narrow(0, end, m).narrow(1, start, len);
+            block.launch_pack(backend, shapes, pass, l21_tr, l21.transposed())?;
+            gemm.dispatch_tr(backend, shapes, pass, prod, l21_tr, l21_tr)?;
+            block.launch_unpack_sub(
+                backend,
+                shapes,
+                pass,
+                l.narrow(0, end, m).narrow(1, end, m),
+                prod,
+            )?;
+        }
+
+        if n > 0 {
+            self.launch_kernel(
+                backend,
+                shapes,
+                pass,
+                &self.chol_zero_upper,
+                None,
+                l,
+                None,
+                None,
+                Some(n * n),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Solves, in-place, `A * X = B` for the multiple right-hand sides `b`, given the Cholesky
+    /// factor `L` of `A` computed by [`Self::potrf`].
+    ///
+    /// The result is unspecified if the factorization failed.
+    pub fn potrs<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        l: impl Into<GpuTensorView<'a, f32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let l = l.into();
+        let b = b.into();
+        let n = check_shape(l.shape());
+        let [b_rows, nrhs, b_mats, b_cubes] = b.shape().size;
+        assert_eq!(b_rows, n, "DenseCholesky: dimension mismatch.");
+        assert_eq!(
+            (b_mats, b_cubes),
+            (1, 1),
+            "DenseCholesky: only one matrix is supported."
+        );
+
+        let params = |start| DenseCholeskyParams {
+            start,
+            len: BLOCK_SIZE.min(n - start),
+            padding: [0; 2],
+        };
+
+        // Forward substitution with `L`.
+        for start in (0..n).step_by(BLOCK_SIZE as usize) {
+            let end = start + BLOCK_SIZE.min(n - start);
+            let kernels = [
+                (&self.chol_solve_lower_diag, nrhs),
+                (&self.chol_solve_lower_update, (n - end) * nrhs),
+            ];
+            for (function, num_threads) in kernels {
+                if num_threads > 0 {
+                    self.launch_kernel(
+                        backend,
+                        shapes,
+                        pass,
+                        function,
+                        Some(params(start)),
+                        l,
+                        None,
+                        Some(b),
+                        Some(num_threads),
+                    )?;
+                }
+            }
+        }
+
+        // Backward substitution with `transpose(L)`.
+        for start in (0..n).step_by(BLOCK_SIZE as usize).rev() {
+            let kernels = [
+                (&self.chol_solve_upper_diag, nrhs),
+                (&self.chol_solve_upper_update, start * nrhs),
+            ];
+            for (function, num_threads) in kernels {
+                if num_threads > 0 {
+                    self.launch_kernel(
+                        backend,
+                        shapes,
+                        pass,
+                        function,
+                        Some(params(start)),
+                        l,
+                        None,
+                        Some(b),
+                        Some(num_threads),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Launches `function` with `num_threads` threads, or with a single workgroup if `None`.
+    #[allow(clippy::too_many_arguments)]
+    fn launch_kernel(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        params: Option<DenseCholeskyParams>,
+        l: GpuTensorView<f32, B>,
+        status: Option<GpuTensorView<u32, B>>,
+        b: Option<GpuTensorView<f32, B>>,
+        num_threads: Option<u32>,
+    ) -> Result<(), B::Error> {
+        let shape_l = l.shape();
+        let shape_status = status.as_ref().map(|status| status.shape());
+        let shape_b = b.as_ref().map(|b| b.shape());
+        for shape in [Some(shape_l), shape_status, shape_b].into_iter().flatten() {
+            shapes.insert(backend, shape)?;
+        }
+        if let Some(params) = params {
+            shapes.insert_params(backend, params)?;
+        }
+
+        let shape = |s: ViewShape| shapes.get(s).unwrap_or_else(|| unreachable!());
+        let args = DenseCholeskyArgs {
+            params: params
+                .map(|params| shapes.get_params(params).unwrap_or_else(|| unreachable!())),
+            shape_l: shape(shape_l),
+            shape_status: shape_status.map(shape),
+            shape_b: shape_b.map(shape),
+            l: l.buffer(),
+            status: status.as_ref().map(|status| status.buffer()),
+            b: b.as_ref().map(|b| b.buffer()),
+        };
+
+        match num_threads {
+            Some(num_threads) => function.launch_capped(backend, pass, &args, num_threads),
+            None => function.launch(backend, pass, &args, [1, 1, 1]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::linalg::{Block, Gemm};
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_dense_cholesky_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_dense_cholesky_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_dense_cholesky_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_dense_cholesky_generic(backend).await;
+    }
+
+    async fn gpu_dense_cholesky_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let cholesky = super::DenseCholesky::from_backend(&backend, &compiler).unwrap();
+        let gemm = Gemm::from_backend(&backend, &compiler).unwrap();
+        let block = Block::from_backend(&backend, &compiler).unwrap();
+
+        const NRHS: usize = 3;
+
+        for (n, spd) in [
+            (1, true),
+            (7, true),
+            (64, true),
+            (129, true),
+            (1031, true),
+            (129, false),
+        ] {
+            println!("Checking {n}x{n} matrix (positive-definite: {spd}).");
+            let m = DMatrix::<f32>::new_random(n, n).add_scalar(-0.5);
+            let mut a = &m * m.transpose() / n as f32 + DMatrix::identity(n, n);
+            if !spd {
+                // The first 100 columns can still be factored.
+                a[(100, 100)] = -1.0;
+            }
+            let b = DMatrix::<f32>::new_random(n, NRHS).add_scalar(-0.5);
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let gpu_l = GpuTensor::matrix(&backend, &a, usage).unwrap();
+            let gpu_x = GpuTensor::matrix(&backend, &b, usage).unwrap();
+            let gpu_status = GpuTensor::vector(&backend, vec![u32::MAX], usage).unwrap();
+            let workspace_len = cholesky.workspace_len(n as u32) as usize;
+            let gpu_workspace =
+                GpuTensor::vector(&backend, vec![0.0f32; workspace_len.max(1)], usage).unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            cholesky
+                .potrf(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    &gemm,
+                    &block,
+                    &gpu_l,
+                    &gpu_status,
+                    &gpu_workspace,
+                )
+                .unwrap();
+            cholesky
+                .potrs(&backend, &mut shapes, &mut pass, &gpu_l, &gpu_x)
+                .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut l = DMatrix::zeros(n, n);
+            let mut x = DMatrix::zeros(n, NRHS);
+            let mut status = [0u32];
+            backend
+                .slow_read_buffer(gpu_l.buffer(), l.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_x.buffer(), x.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_status.buffer(), status.as_mut_slice())
+                .await
+                .unwrap();
+
+            if !spd {
+                assert!(a.clone().cholesky().is_none());
+                assert_eq!(status[0], 101);
+                continue;
+            }
+
+            assert_eq!(status[0], 0);
+
+            // The residual is checked in double precision to be independent from the
+            // conditioning of the random matrix.
+            let a64 = a.cast::<f64>();
+            let x64 = x.cast::<f64>();
+            let residual = (&a64 * &x64 - b.cast::<f64>()).norm();
+            assert!(residual <= 1.0e-4 * a64.norm() * x64.norm());
+
+            if n <= 129 {
+                let l_cpu = a.clone().cholesky().unwrap().unpack();
+                assert_relative_eq!(l, l_cpu, epsilon = 1.0e-3);
+            }
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use crate::linalg::{Block, Gemm};
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+// Alignment (in number of elements) of each part of the workspace. This matches the most
+// restrictive storage buffer offset alignment (256 bytes) for 4-bytes elements.
+const WORKSPACE_ALIGN: u32 = 64;
+// The number of columns factored by each panel of the blocked factorization.
+const BLOCK_SIZE: u32 = 64;
+
+/// Slang module for the LU factorization, with partial pivoting, of a single large square matrix.
+///
+/// The factorization is blocked and right-looking: each panel of 64 columns is factored by a
+/// single workgroup, and the trailing sub-matrix is then updated with [`Gemm`].
+/// This is the dense counterpart of the batched small-matrix [`Lu`](crate::geometry::Lu).
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::dense_lu")]
+pub struct DenseLu<B: Backend> {
+    /// Kernel factoring one panel of the matrix.
+    pub lu_panel: GpuFunction<B>,
+    /// Kernel applying the panel’s row swaps to a set of columns.
+    pub lu_swap_rows: GpuFunction<B>,
+    /// Kernel solving a diagonal block with the unit lower-triangular factor.
+    pub lu_solve_lower_diag: GpuFunction<B>,
+    /// Kernel updating the rows below a diagonal block solved with the lower-triangular factor.
+    pub lu_solve_lower_update: GpuFunction<B>,
+    /// Kernel solving a diagonal block with the upper-triangular factor.
+    pub lu_solve_upper_diag: GpuFunction<B>,
+    /// Kernel updating the rows above a diagonal block solved with the upper-triangular factor.
+    pub lu_solve_upper_update: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct DenseLuParams {
+    start: u32,
+    len: u32,
+    col_start: u32,
+    col_end: u32,
+}
+
+#[derive(ShaderArgs)]
+struct DenseLuArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_lu: Option<&'a B::Buffer<ViewShape>>,
+    shape_pivots: Option<&'a B::Buffer<ViewShape>>,
+    shape_b: Option<&'a B::Buffer<ViewShape>>,
+    lu: Option<B::BufferSlice<'a, f32>>,
+    pivots: Option<B::BufferSlice<'a, u32>>,
+    b: Option<B::BufferSlice<'a, f32>>,
+}
+
+impl<B: Backend> DenseLu<B> {
+    /// The minimum number of elements of the workspace needed by [`Self::getrf`] for
+    /// factoring a `n x n` matrix.
+    pub fn workspace_len(&self, n: u32) -> u32 {
+        let m_pad = n.saturating_sub(BLOCK_SIZE).next_multiple_of(4);
+        (m_pad * BLOCK_SIZE).next_multiple_of(WORKSPACE_ALIGN) * 2 + m_pad * m_pad
+    }
+
+    /// Computes, in-place, the LU factorization with partial pivoting of the square matrix `lu`.
+    ///
+    /// After this call, the strictly lower-triangular part of `lu` contains the unit
+    /// lower-triangular factor `L`, and its upper-triangular part contains `U`, such that
+    /// `P * A = L * U`. The permutation `P` is written to `pivots`, a vector with one element
+    /// per row: the `j`-th row was swapped with the row `pivots[j] >= j`, and these swaps are
+    /// applied in order.
+    ///
+    /// If `A` is singular, some diagonal elements of `U` are zero, and the corresponding columns
+    /// of `L` are left unscaled, like LAPACK’s `getrf`. The `workspace` must be contiguous and
+    /// have at least [`Self::workspace_len`] elements.
+    #[allow(clippy::too_many_arguments)]
+    pub fn getrf<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        gemm: &Gemm<B>,
+        block: &Block<B>,
+        lu: impl Into<GpuTensorView<'a, f32, B>>,
+        pivots: impl Into<GpuTensorView<'a, u32, B>>,
+        workspace: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let lu = lu.into();
+        let pivots = pivots.into();
+        let workspace = workspace.into();
+        let [n, ncols, nmats, ncubes] = lu.shape().size;
+        assert_eq!(n, ncols, "DenseLu: the matrix must be square.");
+        assert_eq!(
+            (nmats, ncubes),
+            (1, 1),
+            "DenseLu: only one matrix is supported."
+        );
+        assert_eq!(
+            pivots.shape().size,
+            [n, 1, 1, 1],
+            "DenseLu: the pivots must contain one element per row."
+        );
+        assert!(
+            workspace.len() >= self.workspace_len(n) as u64,
+            "DenseLu: the workspace is too small."
+        );
+        assert!(
+            workspace.is_contiguous().is_some(),
+            "DenseLu: the workspace must be contiguous."
+        );
+
+        for start in (0..n).step_by(BLOCK_SIZE as usize) {
+            let len = BLOCK_SIZE.min(n - start);
+            let end = start + len;
+            let params = |col_start, col_end| DenseLuParams {
+                start,
+                len,
+                col_start,
+                col_end,
+            };
+
+            self.launch_kernel(
+                backend,
+                shapes,
+                pass,
+                &self.lu_panel,
+                params(0, 0),
+                Some(lu),
+                Some(pivots),
+                None,
+                None,
+            )?;
+
+            // Apply the panel’s row swaps to the columns on both sides of the panel.
+            for (col_start, col_end) in [(0, start), (end, n)] {
+                if col_start < col_end {
+                    self.launch_kernel(
+                        backend,
+                        shapes,
+                        pass,
+                        &self.lu_swap_rows,
+                        params(col_start, col_end),
+                        None,
+                        Some(pivots),
+                        Some(lu),
+                        Some(col_end - col_start),
+                    )?;
+                }
+            }
+
+            if end == n {
+                continue;
+            }
+
+            // Compute U12 = L11⁻¹ A12.
+            self.launch_kernel(
+                backend,
+                shapes,
+                pass,
+                &self.lu_solve_lower_diag,
+                params(end, n),
+                Some(lu),
+                None,
+                Some(lu),
+                Some(n - end),
+            )?;
+
+            // Update A22 -= L21 * U12. The operands are packed into zero-padded blocks matching
+            // the alignment requirements of `Gemm`.
+            let m = n - end;
+            let m_pad = m.next_multiple_of(4);
+            let part_len = (m_pad * BLOCK_SIZE).next_multiple_of(WORKSPACE_ALIGN);
+            let l21 = workspace.view(0, [m_pad, BLOCK_SIZE], [None, None]);
+            let u12 = workspace.view(part_len, [BLOCK_SIZE, m_pad], [None, None]);
+            let prod = workspace.view(part_len * 2, [m_pad, m_pad], [None, None]);
+            let a22 = lu.narrow(0, end, m).narrow(1, end, m);
+            block.launch_pack(
+                backend,
+                shapes,
+                pass,
+                l21,
+                lu.narrow(0, end, m).narrow(1, start, len),
+            )?;
+            block.launch_pack(
+                backend,
+                shapes,
+                pass,
+                u12,
+                lu.narrow(0, start, len).narrow(1, end, m),
+            )?;
+            gemm.dispatch(backend, shapes, pass, prod, l21, u12)?;
+            block.launch_unpack_sub(backend, shapes, pass, a22, prod)?;
+        }
+
+        Ok(())
+    }
+
+    /// Solves, in-place, `A * X = B` for the multiple right-hand sides `b`, given the LU
+    /// factorization of `A` computed by [`Self::getrf`].
+    ///
+    /// The result is unspecified if `A` is singular.
+    pub fn getrs<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        lu: impl Into<GpuTensorView<'a, f32, B>>,
+        pivots: impl Into<GpuTensorView<'a, u32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let lu = lu.into();
+        let pivots = pivots.into();
+        let b = b.into();
+        let [n, ncols, nmats, ncubes] = lu.shape().size;
+        assert_eq!(n, ncols, "DenseLu: the matrix must be square.");
+        assert_eq!(
+            (nmats, ncubes),
+            (1, 1),
+            "DenseLu: only one matrix is supported."
+        );
+        assert_eq!(
+            pivots.shape().size,
+            [n, 1, 1, 1],
+            "DenseLu: the pivots must contain one element per row."
+        );
+        let [b_rows, nrhs, b_mats, b_cubes] = b.shape().size;
+        assert_eq!(b_rows, n, "DenseLu: dimension mismatch.");
+        assert_eq!(
+            (b_mats, b_cubes),
+            (1, 1),
+            "DenseLu: only one matrix is supported."
+        );
+
+        if n == 0 || nrhs == 0 {
+            return Ok(());
+        }
+
+        let params = |start, len| DenseLuParams {
+            start,
+            len,
+            col_start: 0,
+            col_end: nrhs,
+        };
+
+        self.launch_kernel(
+            backend,
+            shapes,
+            pass,
+            &self.lu_swap_rows,
+            params(0, n),
+            None,
+            Some(pivots),
+            Some(b),
+            Some(nrhs),
+        )?;
+
+        // Forward substitution with `L`.
+        for start in (0..n).step_by(BLOCK_SIZE as usize) {
+            let len = BLOCK_SIZE.min(n - start);
+            let kernels = [
+                (&self.lu_solve_lower_diag, nrhs),
+                (&self.lu_solve_lower_update, (n - start - len) * nrhs),
+            ];
+            for (function, num_threads) in kernels {
+                if num_threads > 0 {
+                    self.launch_kernel(
+                        backend,
+                        shapes,
+                        pass,
+                        function,
+                        params(start, len),
+                        Some(lu),
+                        None,
+                        Some(b),
+                        Some(num_threads),
+                    )?;
+                }
+            }
+        }
+
+        // Backward substitution with `U`.
+        for start in (0..n).step_by(BLOCK_SIZE as usize).rev() {
+            let len = BLOCK_SIZE.min(n - start);
+            let kernels = [
+                (&self.lu_solve_upper_diag, nrhs),
+                (&self.lu_solve_upper_update, start * nrhs),
+            ];
+            for (function, num_threads) in kernels {
+                if num_threads > 0 {
+                    self.launch_kernel(
+                        backend,
+                        shapes,
+                        pass,
+                        function,
+                        params(start, len),
+                        Some(lu),
+                        None,
+                        Some(b),
+                        Some(num_threads),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Launches `function` with `num_threads` threads, or with a single workgroup if `None`.
+    #[allow(clippy::too_many_arguments)]
+    fn launch_kernel(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        params: DenseLuParams,
+        lu: Option<GpuTensorView<f32, B>>,
+        pivots: Option<GpuTensorView<u32, B>>,
+        b: Option<GpuTensorView<f32, B>>,
+        num_threads: Option<u32>,
+    ) -> Result<(), B::Error> {
+        let shape_lu = lu.as_ref().map(|lu| lu.shape());
+        let shape_pivots = pivots.as_ref().map(|pivots| pivots.shape());
+        let shape_b = b.as_ref().map(|b| b.shape());
+        for shape in [shape_lu, shape_pivots, shape_b].into_iter().flatten() {
+            shapes.insert(backend, shape)?;
+        }
+        shapes.insert_params(backend, params)?;
+
+        let shape = |s: ViewShape| shapes.get(s).unwrap_or_else(|| unreachable!());
+        let args = DenseLuArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape_lu: shape_lu.map(shape),
+            shape_pivots: shape_pivots.map(shape),
+            shape_b: shape_b.map(shape),
+            lu: lu.as_ref().map(|lu| lu.buffer()),
+            pivots: pivots.as_ref().map(|pivots| pivots.buffer()),
+            b: b.as_ref().map(|b| b.buffer()),
+        };
+
+        match num_threads {
+            Some(num_threads) => function.launch_capped(backend, pass, &args, num_threads),
+            None => function.launch(backend, pass, &args, [1, 1, 1]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::linalg::{Block, Gemm};
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    // The column of the dominant element of the row `i` of `permuted_dominant_matrix(n)`.
+    fn dominant_col(n: usize, i: usize) -> usize {
+        // NOTE: this is a permutation as long as `n` isn’t a multiple of 7.
+        (7 * i + 3) % n
+    }
+
+    // A random matrix with one dominant element per row, at a column that isn’t the row index.
+    // This is well-conditioned, but still requires row interchanges at almost every step.
+    fn permuted_dominant_matrix(n: usize) -> DMatrix<f32> {
+        let mut a = DMatrix::<f32>::new_random(n, n).add_scalar(-0.5);
+        for i in 0..n {
+            a[(i, dominant_col(n, i))] += n as f32;
+        }
+        a
+    }
+
+    // The pivots chosen by partial pivoting on `permuted_dominant_matrix(n)`: the pivot of the
+    // column `j` is the (current position of the) row with its dominant element in that column.
+    fn permuted_dominant_pivots(n: usize) -> Vec<u32> {
+        let mut row_of_col = vec![0; n];
+        for i in 0..n {
+            row_of_col[dominant_col(n, i)] = i;
+        }
+        let mut row_at: Vec<_> = (0..n).collect();
+        let mut pos_of: Vec<_> = (0..n).collect();
+        (0..n)
+            .map(|j| {
+                let p = pos_of[row_of_col[j]];
+                row_at.swap(j, p);
+                pos_of[row_at[j]] = j;
+                pos_of[row_at[p]] = p;
+                p as u32
+            })
+            .collect()
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_dense_lu_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_dense_lu_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_dense_lu_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_dense_lu_generic(backend).await;
+    }
+
+    async fn gpu_dense_lu_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let lu = super::DenseLu::from_backend(&backend, &compiler).unwrap();
+        let gemm = Gemm::from_backend(&backend, &compiler).unwrap();
+        let block = Block::from_backend(&backend, &compiler).unwrap();
+
+        const NRHS: usize = 3;
+        // Above this size, computing the reference factorization on the cpu is too slow.
+        const MAX_CPU_LU_DIM: usize = 129;
+
+        // NOTE: 2049 isn’t a multiple of the block size, so the trailing blocks are padded.
+        for n in [1, 7, 64, 129, 1031, 2049] {
+            println!("Checking {n}x{n} matrix.");
+            let (a, b, expected_x) = if n <= MAX_CPU_LU_DIM {
+                let a = DMatrix::<f32>::new_random(n, n).add_scalar(-0.5);
+                let b = DMatrix::<f32>::new_random(n, NRHS).add_scalar(-0.5);
+                (a, b, None)
+            } else {
+                // The large matrices are well-conditioned, with a known solution, so the gpu
+                // solution can be compared to it directly.
+                let a = permuted_dominant_matrix(n);
+                let x = DMatrix::<f64>::new_random(n, NRHS).add_scalar(-0.5);
+                let b = (a.cast::<f64>() * &x).cast::<f32>();
+                (a, b, Some(x))
+            };
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let gpu_lu = GpuTensor::matrix(&backend, &a, usage).unwrap();
+            let gpu_x = GpuTensor::matrix(&backend, &b, usage).unwrap();
+            let gpu_pivots = GpuTensor::vector(&backend, vec![0u32; n], usage).unwrap();
+            let workspace_len = lu.workspace_len(n as u32) as usize;
+            let gpu_workspace =
+                GpuTensor::vector(&backend, vec![0.0f32; workspace_len.max(1)], usage).unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            lu.getrf(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gemm,
+                &block,
+                &gpu_lu,
+                &gpu_pivots,
+                &gpu_workspace,
+            )
+            .unwrap();
+            lu.getrs(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gpu_lu,
+                &gpu_pivots,
+                &gpu_x,
+            )
+            .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut factors = DMatrix::zeros(n, n);
+            let mut x = DMatrix::zeros(n, NRHS);
+            let mut pivots = vec![0u32; n];
+            backend
+                .slow_read_buffer(gpu_lu.buffer(), factors.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_x.buffer(), x.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_pivots.buffer(), pivots.as_mut_slice())
+                .await
+                .unwrap();
+
+            // The residual is checked in double precision to be independent from the
+            // conditioning of the random matrix.
+            let a64 = a.cast::<f64>();
+            let x64 = x.cast::<f64>();
+            let residual = (&a64 * &x64 - b.cast::<f64>()).norm();
+            assert!(residual <= 1.0e-4 * a64.norm() * x64.norm());
+
+            if let Some(expected_x) = expected_x {
+                assert!((x64 - &expected_x).norm() <= 1.0e-4 * expected_x.norm());
+                assert_eq!(pivots, permuted_dominant_pivots(n));
+            } else {
+                let lu_cpu = a.clone().lu();
+
+                let mut permutation = DMatrix::<f32>::identity(n, n);
+                for (j, p) in pivots.iter().enumerate() {
+                    permutation.swap_rows(j, *p as usize);
+                }
+                let mut expected_permutation = DMatrix::<f32>::identity(n, n);
+                lu_cpu.p().permute_rows(&mut expected_permutation);
+                assert_eq!(permutation, expected_permutation);
+
+                let mut l = factors.lower_triangle();
+                l.fill_diagonal(1.0);
+                let u = factors.upper_triangle();
+                assert_relative_eq!(l, lu_cpu.l(), epsilon = 1.0e-3);
+                assert_relative_eq!(u, lu_cpu.u(), epsilon = 1.0e-3);
+            }
+        }
+    }
+}
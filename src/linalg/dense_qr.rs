@@ -0,0 +1,647 @@
+use crate::linalg::{Block, Diagonal, Gemm, MatrixMode, Triangle, Trsm};
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+// Alignment (in number of elements) of each part of the workspace. This matches the most
+// restrictive storage buffer offset alignment (256 bytes) for 4-bytes elements.
+const WORKSPACE_ALIGN: u32 = 64;
+// The number of columns factored by each panel of the blocked factorization.
+// NOTE: this must match `BLOCK_SIZE` from `dense_qr.slang`.
+const BLOCK_SIZE: u32 = 64;
+
+/// Slang module for the Householder QR factorization of a single large tall matrix, and the
+/// associated least-squares solver.
+///
+/// The factorization is blocked: each panel of 64 columns is factored by a single workgroup, and
+/// its reflectors are then applied to the trailing sub-matrix, in their compact WY
+/// representation, with [`Gemm`].
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::dense_qr")]
+pub struct DenseQr<B: Backend> {
+    /// Kernel factoring one panel of the matrix.
+    pub qr_panel: GpuFunction<B>,
+    /// Kernel copying the reflectors of a panel into a zero-padded block.
+    pub qr_pack_v: GpuFunction<B>,
+    /// Kernel computing the triangular factor of the compact WY representation of a panel.
+    pub qr_form_t: GpuFunction<B>,
+    /// Kernel setting a matrix to the first columns of the identity.
+    pub qr_set_identity: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct DenseQrParams {
+    start: u32,
+    len: u32,
+    padding: [u32; 2],
+}
+
+#[derive(ShaderArgs)]
+struct DenseQrArgs<'a, B: Backend> {
+    params: Option<&'a B::Buffer<u32>>,
+    shape_a: Option<&'a B::Buffer<ViewShape>>,
+    shape_tau: Option<&'a B::Buffer<ViewShape>>,
+    shape_v: Option<&'a B::Buffer<ViewShape>>,
+    shape_t: Option<&'a B::Buffer<ViewShape>>,
+    shape_q: Option<&'a B::Buffer<ViewShape>>,
+    a: Option<B::BufferSlice<'a, f32>>,
+    tau: Option<B::BufferSlice<'a, f32>>,
+    v: Option<B::BufferSlice<'a, f32>>,
+    t: Option<B::BufferSlice<'a, f32>>,
+    q: Option<B::BufferSlice<'a, f32>>,
+}
+
+// The tensors bound by a kernel of the `DenseQr` shader.
+struct DenseQrBindings<'a, B: Backend> {
+    a: Option<GpuTensorView<'a, f32, B>>,
+    tau: Option<GpuTensorView<'a, f32, B>>,
+    v: Option<GpuTensorView<'a, f32, B>>,
+    t: Option<GpuTensorView<'a, f32, B>>,
+    q: Option<GpuTensorView<'a, f32, B>>,
+}
+
+// NOTE: not derived since it would require `B: Default`.
+impl<B: Backend> Default for DenseQrBindings<'_, B> {
+    fn default() -> Self {
+        Self {
+            a: None,
+            tau: None,
+            v: None,
+            t: None,
+            q: None,
+        }
+    }
+}
+
+/// Checks that `a` is a single tall matrix and `tau` has one element per column of `a`.
+///
+/// Returns the dimensions of `a`.
+fn check_shapes(a: ViewShape, tau: ViewShape) -> (u32, u32) {
+    let [m, n, nmats, ncubes] = a.size;
+    assert!(
+        m >= n,
+        "DenseQr: the matrix must have at least as many rows as columns."
+    );
+    assert_eq!(
+        (nmats, ncubes),
+        (1, 1),
+        "DenseQr: only one matrix is supported."
+    );
+    assert_eq!(
+        tau.size,
+        [n, 1, 1, 1],
+        "DenseQr: `tau` must contain one element per column."
+    );
+    (m, n)
+}
+
+impl<B: Backend> DenseQr<B> {
+    /// The minimum number of elements of the workspace needed for factoring a `m x n` matrix,
+    /// or for applying the `Q` factor of a matrix with `m` rows to a `m x n` matrix.
+    pub fn workspace_len(&self, m: u32, n: u32) -> u32 {
+        let m_pad = m.next_multiple_of(4);
+        let n_pad = n.next_multiple_of(4);
+        let align = |len: u32| len.next_multiple_of(WORKSPACE_ALIGN);
+        align(m_pad * BLOCK_SIZE)
+            + align(m_pad * n_pad)
+            + align(BLOCK_SIZE * n_pad) * 2
+            + BLOCK_SIZE * BLOCK_SIZE
+    }
+
+    /// Computes, in-place, the Householder QR factorization `A = Q * R` of the `m x n` matrix
+    /// `a`, with `m >= n`.
+    ///
+    /// After this call, the upper-triangular part of `a` contains `R`. Like LAPACK’s `geqrf`,
+    /// `Q = H_0 * ... * H_{n - 1}` is stored implicitly as a product of Householder reflectors
+    /// `H_j = I - tau[j] * v_j * transpose(v_j)`, where `v_j[j] = 1`, `v_j[..j] = 0`, and
+    /// `v_j[j + 1..]` is stored below the diagonal of `a`. Use [`Self::orgqr`] or
+    /// [`Self::ormqr`] to form or apply `Q`.
+    ///
+    /// The `workspace` must be contiguous and have at least [`Self::workspace_len`]`(m, n)`
+    /// elements.
+    #[allow(clippy::too_many_arguments)]
+    pub fn geqrf<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        gemm: &Gemm<B>,
+        block: &Block<B>,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        tau: impl Into<GpuTensorView<'a, f32, B>>,
+        workspace: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let a = a.into();
+        let tau = tau.into();
+        let workspace = workspace.into();
+        let (m, n) = check_shapes(a.shape(), tau.shape());
+        self.check_workspace(workspace, m, n);
+
+        for start in (0..n).step_by(BLOCK_SIZE as usize) {
+            let len = BLOCK_SIZE.min(n - start);
+            let end = start + len;
+            let params = DenseQrParams {
+                start,
+                len,
+                padding: [0; 2],
+            };
+            let bindings = DenseQrBindings {
+                a: Some(a),
+                tau: Some(tau),
+                ..Default::default()
+            };
+            self.launch_kernel(
+                backend,
+                shapes,
+                pass,
+                &self.qr_panel,
+                Some(params),
+                bindings,
+                None,
+            )?;
+
+            if end < n {
+                let trailing = a.narrow(1, end, n - end);
+                self.apply_block_reflector(
+                    backend,
+                    shapes,
+                    pass,
+                    gemm,
+                    block,
+                    MatrixMode::Transposed,
+                    params,
+                    a,
+                    tau,
+                    trailing,
+                    workspace,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes, in-place, `op(Q) * c` where `Q` is the orthogonal factor computed by
+    /// [`Self::geqrf`] from `a` and `tau`, and `op(Q)` is `Q` or its transpose depending on
+    /// `mode`.
+    ///
+    /// The matrix `c` must have as many rows as `a`. The `workspace` must be contiguous and have
+    /// at least [`Self::workspace_len`]`(m, ncols)` elements, where `m x ncols` is the shape of
+    /// `c`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn ormqr<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        gemm: &Gemm<B>,
+        block: &Block<B>,
+        mode: MatrixMode,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        tau: impl Into<GpuTensorView<'a, f32, B>>,
+        c: impl Into<GpuTensorView<'a, f32, B>>,
+        workspace: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let a = a.into();
+        let tau = tau.into();
+        let c = c.into();
+        let workspace = workspace.into();
+        let (m, n) = check_shapes(a.shape(), tau.shape());
+        let [c_rows, ncols, c_mats, c_cubes] = c.shape().size;
+        assert_eq!(c_rows, m, "DenseQr: dimension mismatch.");
+        assert_eq!(
+            (c_mats, c_cubes),
+            (1, 1),
+            "DenseQr: only one matrix is supported."
+        );
+        self.check_workspace(workspace, m, ncols);
+
+        if ncols == 0 {
+            return Ok(());
+        }
+
+        // `transpose(Q) = H_{n - 1} * ... * H_0` applies the panels in order, and `Q` in
+        // reverse order.
+        let mut starts: Vec<_> = (0..n).step_by(BLOCK_SIZE as usize).collect();
+        if mode == MatrixMode::Normal {
+            starts.reverse();
+        }
+
+        for start in starts {
+            let params = DenseQrParams {
+                start,
+                len: BLOCK_SIZE.min(n - start),
+                padding: [0; 2],
+            };
+            self.apply_block_reflector(
+                backend, shapes, pass, gemm, block, mode, params, a, tau, c, workspace,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Forms explicitly the first columns of the orthogonal factor `Q` computed by
+    /// [`Self::geqrf`] from `a` and `tau`.
+    ///
+    /// The matrix `q` must have as many rows as `a`, and usually has as many columns as `a` for
+    /// the thin `Q` factor. The `workspace` must be contiguous and have at least
+    /// [`Self::workspace_len`]`(m, ncols)` elements, where `m x ncols` is the shape of `q`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn orgqr<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        gemm: &Gemm<B>,
+        block: &Block<B>,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        tau: impl Into<GpuTensorView<'a, f32, B>>,
+        q: impl Into<GpuTensorView<'a, f32, B>>,
+        workspace: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let q = q.into();
+        if q.len() != 0 {
+            let bindings = DenseQrBindings {
+                q: Some(q),
+                ..Default::default()
+            };
+            let num_threads = Some(q.len() as u32);
+            self.launch_kernel(
+                backend,
+                shapes,
+                pass,
+                &self.qr_set_identity,
+                None,
+                bindings,
+                num_threads,
+            )?;
+        }
+        self.ormqr(
+            backend,
+            shapes,
+            pass,
+            gemm,
+            block,
+            MatrixMode::Normal,
+            a,
+            tau,
+            q,
+            workspace,
+        )
+    }
+
+    /// Solves the linear least-squares problem `min ‖a * x - b‖` for the `m x n` matrix `a`,
+    /// with `m >= n` and full rank, and the multiple right-hand sides `b`.
+    ///
+    /// This factors `a` in-place with [`Self::geqrf`], writing the reflector coefficients to
+    /// `tau`. The solution `x` is written to the first `n` rows of `b`, and the norms of the
+    /// remaining rows of each column are the residual norms. The `workspace` must be contiguous
+    /// and have at least [`Self::workspace_len`]`(m, max(n, nrhs))` elements, where `m x nrhs`
+    /// is the shape of `b`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn lstsq<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        gemm: &Gemm<B>,
+        block: &Block<B>,
+        trsm: &Trsm<B>,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        tau: impl Into<GpuTensorView<'a, f32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+        workspace: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let a = a.into();
+        let tau = tau.into();
+        let b = b.into();
+        let workspace = workspace.into();
+        let n = a.shape().size[1];
+
+        self.geqrf(backend, shapes, pass, gemm, block, a, tau, workspace)?;
+        self.ormqr(
+            backend,
+            shapes,
+            pass,
+            gemm,
+            block,
+            MatrixMode::Transposed,
+            a,
+            tau,
+            b,
+            workspace,
+        )?;
+        trsm.trsm(
+            backend,
+            shapes,
+            pass,
+            Triangle::Upper,
+            MatrixMode::Normal,
+            Diagonal::NonUnit,
+            a.narrow(0, 0, n),
+            b.narrow(0, 0, n),
+        )
+    }
+
+    fn check_workspace(&self, workspace: GpuTensorView<f32, B>, m: u32, n: u32) {
+        assert!(
+            workspace.len() >= self.workspace_len(m, n) as u64,
+            "DenseQr: the workspace is too small."
+        );
+        assert!(
+            workspace.is_contiguous().is_some(),
+            "DenseQr: the workspace must be contiguous."
+        );
+    }
+
+    // Computes, in-place, `c -= v * op(t) * transpose(v) * c` for the rows of `c` starting
+    // at `params.start`, i.e., applies `op(I - v * t * transpose(v))` where `v` and `t` are the
+    // compact WY representation of the panel `params`.
+    #[allow(clippy::too_many_arguments)]
+    fn apply_block_reflector(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        gemm: &Gemm<B>,
+        block: &Block<B>,
+        mode: MatrixMode,
+        params: DenseQrParams,
+        a: GpuTensorView<f32, B>,
+        tau: GpuTensorView<f32, B>,
+        c: GpuTensorView<f32, B>,
+        workspace: GpuTensorView<f32, B>,
+    ) -> Result<(), B::Error> {
+        let [m, ncols, _, _] = c.shape().size;
+        let nrows = m - params.start;
+        let m_pad = nrows.next_multiple_of(4);
+        let c_pad = ncols.next_multiple_of(4);
+        let align = |len: u32| len.next_multiple_of(WORKSPACE_ALIGN);
+
+        // Split the workspace. The product `v * w2` overwrites the packed `c`.
+        let v_offset = 0;
+        let c_offset = v_offset + align(m_pad * BLOCK_SIZE);
+        let w_offset = c_offset + align(m_pad * c_pad);
+        let w2_offset = w_offset + align(BLOCK_SIZE * c_pad);
+        let t_offset = w2_offset + align(BLOCK_SIZE * c_pad);
+        let v = workspace.view(v_offset, [m_pad, BLOCK_SIZE], [None, None]);
+        let packed_c = workspace.view(c_offset, [m_pad, c_pad], [None, None]);
+        let w = workspace.view(w_offset, [BLOCK_SIZE, c_pad], [None, None]);
+        let w2 = workspace.view(w2_offset, [BLOCK_SIZE, c_pad], [None, None]);
+        let t = workspace.view(t_offset, [BLOCK_SIZE, BLOCK_SIZE], [None, None]);
+
+        let bindings = DenseQrBindings {
+            a: Some(a),
+            v: Some(v),
+            ..Default::default()
+        };
+        let num_threads = Some(m_pad * BLOCK_SIZE);
+        self.launch_kernel(
+            backend,
+            shapes,
+            pass,
+            &self.qr_pack_v,
+            Some(params),
+            bindings,
+            num_threads,
+        )?;
+        let bindings = DenseQrBindings {
+            tau: Some(tau),
+            v: Some(v),
+            t: Some(t),
+            ..Default::default()
+        };
+        self.launch_kernel(
+            backend,
+            shapes,
+            pass,
+            &self.qr_form_t,
+            Some(params),
+            bindings,
+            None,
+        )?;
+
+        let c_rows = c.narrow(0, params.start, nrows);
+        block.launch_pack(backend, shapes, pass, packed_c, c_rows)?;
+        gemm.dispatch_tr(backend, shapes, pass, w, v, packed_c)?;
+        match mode {
+            MatrixMode::Normal => gemm.dispatch(backend, shapes, pass, w2, t, w)?,
+            MatrixMode::Transposed => gemm.dispatch_tr(backend, shapes, pass, w2, t, w)?,
+        }
+        gemm.dispatch(backend, shapes, pass, packed_c, v, w2)?;
+        block.launch_unpack_sub(backend, shapes, pass, c_rows, packed_c)
+    }
+
+    // Launches `function` with `num_threads` threads, or with a single workgroup if `None`.
+    #[allow(clippy::too_many_arguments)]
+    fn launch_kernel(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        params: Option<DenseQrParams>,
+        bindings: DenseQrBindings<B>,
+        num_threads: Option<u32>,
+    ) -> Result<(), B::Error> {
+        let DenseQrBindings { a, tau, v, t, q } = bindings;
+        let [shape_a, shape_tau, shape_v, shape_t, shape_q] =
+            [a, tau, v, t, q].map(|tensor| tensor.map(|tensor| tensor.shape()));
+        for shape in [shape_a, shape_tau, shape_v, shape_t, shape_q]
+            .into_iter()
+            .flatten()
+        {
+            shapes.insert(backend, shape)?;
+        }
+        if let Some(params) = params {
+            shapes.insert_params(backend, params)?;
+        }
+
+        let shape = |s: ViewShape| shapes.get(s).unwrap_or_else(|| unreachable!());
+        let args = DenseQrArgs {
+            params: params
+                .map(|params| shapes.get_params(params).unwrap_or_else(|| unreachable!())),
+            shape_a: shape_a.map(shape),
+            shape_tau: shape_tau.map(shape),
+            shape_v: shape_v.map(shape),
+            shape_t: shape_t.map(shape),
+            shape_q: shape_q.map(shape),
+            a: a.as_ref().map(|a| a.buffer()),
+            tau: tau.as_ref().map(|tau| tau.buffer()),
+            v: v.as_ref().map(|v| v.buffer()),
+            t: t.as_ref().map(|t| t.buffer()),
+            q: q.as_ref().map(|q| q.buffer()),
+        };
+
+        match num_threads {
+            Some(num_threads) => function.launch_capped(backend, pass, &args, num_threads),
+            None => function.launch(backend, pass, &args, [1, 1, 1]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::linalg::{Block, Gemm, MatrixMode, Trsm};
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_dense_qr_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_dense_qr_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_dense_qr_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_dense_qr_generic(backend).await;
+    }
+
+    async fn gpu_dense_qr_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let qr = super::DenseQr::from_backend(&backend, &compiler).unwrap();
+        let gemm = Gemm::from_backend(&backend, &compiler).unwrap();
+        let block = Block::from_backend(&backend, &compiler).unwrap();
+        let trsm = Trsm::from_backend(&backend, &compiler).unwrap();
+
+        const NRHS: usize = 3;
+
+        for (m, n) in [(1, 1), (7, 3), (64, 64), (200, 129), (1031, 150)] {
+            println!("Checking {m}x{n} matrix.");
+            let a = DMatrix::<f32>::new_random(m, n).add_scalar(-0.5);
+            let b = DMatrix::<f32>::new_random(m, NRHS).add_scalar(-0.5);
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let gpu_qr = GpuTensor::matrix(&backend, &a, usage).unwrap();
+            let gpu_q = GpuTensor::matrix(&backend, &DMatrix::<f32>::zeros(m, n), usage).unwrap();
+            let gpu_tau = GpuTensor::vector(&backend, vec![0.0f32; n], usage).unwrap();
+            let gpu_lstsq_a = GpuTensor::matrix(&backend, &a, usage).unwrap();
+            let gpu_lstsq_tau = GpuTensor::vector(&backend, vec![0.0f32; n], usage).unwrap();
+            let gpu_x = GpuTensor::matrix(&backend, &b, usage).unwrap();
+            let workspace_len = qr.workspace_len(m as u32, n.max(NRHS) as u32) as usize;
+            let gpu_workspace =
+                GpuTensor::vector(&backend, vec![0.0f32; workspace_len], usage).unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            qr.geqrf(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gemm,
+                &block,
+                &gpu_qr,
+                &gpu_tau,
+                &gpu_workspace,
+            )
+            .unwrap();
+            qr.orgqr(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gemm,
+                &block,
+                &gpu_qr,
+                &gpu_tau,
+                &gpu_q,
+                &gpu_workspace,
+            )
+            .unwrap();
+            qr.lstsq(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gemm,
+                &block,
+                &trsm,
+                &gpu_lstsq_a,
+                &gpu_lstsq_tau,
+                &gpu_x,
+                &gpu_workspace,
+            )
+            .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut factors = DMatrix::zeros(m, n);
+            let mut q = DMatrix::zeros(m, n);
+            let mut x = DMatrix::zeros(m, NRHS);
+            backend
+                .slow_read_buffer(gpu_qr.buffer(), factors.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_q.buffer(), q.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_x.buffer(), x.as_mut_slice())
+                .await
+                .unwrap();
+
+            let r = factors.rows(0, n).upper_triangle();
+            assert_relative_eq!(q.tr_mul(&q), DMatrix::identity(n, n), epsilon = 1.0e-3);
+            assert_relative_eq!(&q * &r, a, epsilon = 1.0e-3);
+
+            // `R` is unique up to the signs of its rows.
+            let r_cpu = a.clone().qr().r();
+            assert_relative_eq!(r.abs(), r_cpu.abs(), epsilon = 1.0e-3);
+
+            // Compare with the solution of the normal equations, in double precision.
+            let a64 = a.cast::<f64>();
+            let x_cpu = a64
+                .tr_mul(&a64)
+                .cholesky()
+                .unwrap()
+                .solve(&a64.tr_mul(&b.cast::<f64>()));
+            let x = x.rows(0, n).cast::<f64>();
+            assert!((&x - &x_cpu).norm() <= 1.0e-3 * x_cpu.norm());
+
+            // Applying `transpose(Q)` then `Q` is the identity.
+            let c = DMatrix::<f32>::new_random(m, NRHS);
+            let gpu_c = GpuTensor::matrix(&backend, &c, usage).unwrap();
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            for mode in [MatrixMode::Transposed, MatrixMode::Normal] {
+                qr.ormqr(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    &gemm,
+                    &block,
+                    mode,
+                    &gpu_qr,
+                    &gpu_tau,
+                    &gpu_c,
+                    &gpu_workspace,
+                )
+                .unwrap();
+            }
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+            backend.submit(encoder).unwrap();
+
+            let mut result = DMatrix::zeros(m, NRHS);
+            backend
+                .slow_read_buffer(gpu_c.buffer(), result.as_mut_slice())
+                .await
+                .unwrap();
+            assert_relative_eq!(result, c, epsilon = 1.0e-3);
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::{Backend, DeviceValue};
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// The comparison operator for [`Elementwise::launch_compare`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum CompareVariant {
+    /// Less than: `out = a < b`
+    Lt,
+    /// Less than or equal: `out = a <= b`
+    Le,
+    /// Equal: `out = a == b`
+    Eq,
+    /// Not equal: `out = a != b`
+    Ne,
+    /// Greater than: `out = a > b`
+    Gt,
+    /// Greater than or equal: `out = a >= b`
+    Ge,
+}
+
+impl CompareVariant {
+    fn id(self) -> u32 {
+        match self {
+            Self::Lt => 0,
+            Self::Le => 1,
+            Self::Eq => 2,
+            Self::Ne => 3,
+            Self::Gt => 4,
+            Self::Ge => 5,
+        }
+    }
+
+    /// Applies this comparison on the CPU.
+    pub fn compare(self, a: f32, b: f32) -> bool {
+        match self {
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+        }
+    }
+}
+
+/// Slang module for element-wise comparisons, selection, clamping, and interpolation.
+///
+/// Masks are `u32` tensors where any non-zero value is considered `true`. The inputs of each
+/// operation are broadcast to the output shape following the same rules as
+/// [`OpAssign::launch`](crate::linalg::OpAssign::launch): the output shape must be a multiple
+/// of the input shapes.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::elementwise")]
+pub struct Elementwise<B: Backend> {
+    /// Kernel comparing two tensors into a `u32` mask.
+    pub compare: GpuFunction<B>,
+    /// Kernel selecting the elements of one of two tensors depending on a mask.
+    pub select_where: GpuFunction<B>,
+    /// Kernel for clamping in-place the elements of a tensor.
+    pub clamp_assign: GpuFunction<B>,
+    /// Kernel for computing in-place the linear interpolation between two tensors.
+    pub lerp_assign: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct CompareParams {
+    op: u32,
+    padding: [u32; 3],
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ClampParams {
+    min: f32,
+    max: f32,
+    padding: [u32; 2],
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct LerpParams {
+    weight: f32,
+    padding: [u32; 3],
+}
+
+#[derive(ShaderArgs)]
+struct ElementwiseArgs<'a, T: DeviceValue, B: Backend> {
+    params: Option<&'a B::Buffer<u32>>,
+    shape_out: Option<&'a B::Buffer<ViewShape>>,
+    shape_mask: Option<&'a B::Buffer<ViewShape>>,
+    shape_a: Option<&'a B::Buffer<ViewShape>>,
+    shape_b: Option<&'a B::Buffer<ViewShape>>,
+    out: Option<B::BufferSlice<'a, T>>,
+    mask: Option<B::BufferSlice<'a, u32>>,
+    a: Option<B::BufferSlice<'a, f32>>,
+    b: Option<B::BufferSlice<'a, f32>>,
+}
+
+fn assert_broadcast(op: &str, shape_out: ViewShape, shape_in: ViewShape) {
+    assert!(
+        shape_out.is_multiple_of(shape_in),
+        "{op}: the output shape {:?} must be a multiple of the input shape {:?}.",
+        shape_out.size,
+        shape_in.size
+    );
+}
+
+impl<B: Backend> Elementwise<B> {
+    /// Launches the kernel computing the `u32` mask `out = a <op> b`, where `<op>` is the
+    /// comparison given by `variant`.
+    ///
+    /// Each element of `out` is set to `1` if the comparison is true, and `0` otherwise.
+    pub fn launch_compare<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: CompareVariant,
+        out: impl Into<GpuTensorView<'a, u32, B>>,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let a = a.into();
+        let b = b.into();
+        let (shape_out, shape_a, shape_b) = (out.shape(), a.shape(), b.shape());
+        assert_broadcast("Compare", shape_out, shape_a);
+        assert_broadcast("Compare", shape_out, shape_b);
+
+        let params = CompareParams {
+            op: variant.id(),
+            padding: [0; 3],
+        };
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_a)?;
+        shapes.insert(backend, shape_b)?;
+        shapes.insert_params(backend, params)?;
+
+        let args = ElementwiseArgs {
+            params: Some(shapes.get_params(params).unwrap_or_else(|| unreachable!())),
+            shape_out: Some(shapes.get(shape_out).unwrap_or_else(|| unreachable!())),
+            shape_mask: None,
+            shape_a: Some(shapes.get(shape_a).unwrap_or_else(|| unreachable!())),
+            shape_b: Some(shapes.get(shape_b).unwrap_or_else(|| unreachable!())),
+            out: Some(out.buffer()),
+            mask: None,
+            a: Some(a.buffer()),
+            b: Some(b.buffer()),
+        };
+        self.compare
+            .launch_capped(backend, pass, &args, out.len() as u32)
+    }
+
+    /// Launches the kernel computing `out = where(mask, a, b)`.
+    ///
+    /// Each element of `out` is set to the corresponding element of `a` if `mask` is non-zero,
+    /// and to the corresponding element of `b` otherwise.
+    pub fn launch_where<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        mask: impl Into<GpuTensorView<'a, u32, B>>,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let mask = mask.into();
+        let a = a.into();
+        let b = b.into();
+        let (shape_out, shape_mask) = (out.shape(), mask.shape());
+        let (shape_a, shape_b) = (a.shape(), b.shape());
+        assert_broadcast("Where", shape_out, shape_mask);
+        assert_broadcast("Where", shape_out, shape_a);
+        assert_broadcast("Where", shape_out, shape_b);
+
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_mask)?;
+        shapes.insert(backend, shape_a)?;
+        shapes.insert(backend, shape_b)?;
+
+        let args = ElementwiseArgs {
+            params: None,
+            shape_out: Some(shapes.get(shape_out).unwrap_or_else(|| unreachable!())),
+            shape_mask: Some(shapes.get(shape_mask).unwrap_or_else(|| unreachable!())),
+            shape_a: Some(shapes.get(shape_a).unwrap_or_else(|| unreachable!())),
+            shape_b: Some(shapes.get(shape_b).unwrap_or_else(|| unreachable!())),
+            out: Some(out.buffer()),
+            mask: Some(mask.buffer()),
+            a: Some(a.buffer()),
+            b: Some(b.buffer()),
+        };
+        self.select_where
+            .launch_capped(backend, pass, &args, out.len() as u32)
+    }
+
+    /// Launches the kernel computing in-place `a = clamp(a, min, max)`.
+    pub fn launch_clamp<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        min: f32,
+        max: f32,
+    ) -> Result<(), B::Error> {
+        let a = a.into();
+        let shape_a = a.shape();
+        let params = ClampParams {
+            min,
+            max,
+            padding: [0; 2],
+        };
+        shapes.insert(backend, shape_a)?;
+        shapes.insert_params(backend, params)?;
+
+        let args = ElementwiseArgs::<f32, B> {
+            params: Some(shapes.get_params(params).unwrap_or_else(|| unreachable!())),
+            shape_out: None,
+            shape_mask: None,
+            shape_a: Some(shapes.get(shape_a).unwrap_or_else(|| unreachable!())),
+            shape_b: None,
+            out: None,
+            mask: None,
+            a: Some(a.buffer()),
+            b: None,
+        };
+        self.clamp_assign
+            .launch_capped(backend, pass, &args, a.len() as u32)
+    }
+
+    /// Launches the kernel computing in-place the linear interpolation
+    /// `a = a + weight * (b - a)`.
+    pub fn launch_lerp<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+        weight: f32,
+    ) -> Result<(), B::Error> {
+        let a = a.into();
+        let b = b.into();
+        let (shape_a, shape_b) = (a.shape(), b.shape());
+        assert_broadcast("Lerp", shape_a, shape_b);
+
+        let params = LerpParams {
+            weight,
+            padding: [0; 3],
+        };
+        shapes.insert(backend, shape_a)?;
+        shapes.insert(backend, shape_b)?;
+        shapes.insert_params(backend, params)?;
+
+        let args = ElementwiseArgs::<f32, B> {
+            params: Some(shapes.get_params(params).unwrap_or_else(|| unreachable!())),
+            shape_out: None,
+            shape_mask: None,
+            shape_a: Some(shapes.get(shape_a).unwrap_or_else(|| unreachable!())),
+            shape_b: Some(shapes.get(shape_b).unwrap_or_else(|| unreachable!())),
+            out: None,
+            mask: None,
+            a: Some(a.buffer()),
+            b: Some(b.buffer()),
+        };
+        self.lerp_assign
+            .launch_capped(backend, pass, &args, a.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CompareVariant;
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_elementwise_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_elementwise_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_elementwise_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_elementwise_generic(backend).await;
+    }
+
+    async fn gpu_elementwise_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let elementwise = super::Elementwise::from_backend(&backend, &compiler).unwrap();
+
+        const NROWS: usize = 301;
+        const NCOLS: usize = 37;
+
+        let variants = [
+            CompareVariant::Lt,
+            CompareVariant::Le,
+            CompareVariant::Eq,
+            CompareVariant::Ne,
+            CompareVariant::Gt,
+            CompareVariant::Ge,
+        ];
+
+        // Rounded values so the equality comparisons are exercised too.
+        let a = DMatrix::<f32>::new_random(NROWS, NCOLS).map(|x| (x * 4.0).round());
+        let b = DMatrix::<f32>::new_random(NROWS, NCOLS).map(|x| (x * 4.0).round());
+        // The row vector `c` is broadcast along the rows.
+        let c = DMatrix::<f32>::new_random(1, NCOLS);
+        let mask = DMatrix::<u32>::from_fn(NROWS, NCOLS, |i, j| ((i + j) % 3 == 0) as u32);
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let gpu_a = GpuTensor::matrix(&backend, &a, usage).unwrap();
+        let gpu_b = GpuTensor::matrix(&backend, &b, usage).unwrap();
+        let gpu_c = GpuTensor::matrix(&backend, &c, usage).unwrap();
+        let gpu_mask = GpuTensor::matrix(&backend, &mask, usage).unwrap();
+        let gpu_masks: Vec<_> = variants
+            .iter()
+            .map(|_| GpuTensor::matrix(&backend, &DMatrix::<u32>::zeros(NROWS, NCOLS), usage))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let zeros = DMatrix::<f32>::zeros(NROWS, NCOLS);
+        let gpu_where = GpuTensor::matrix(&backend, &zeros, usage).unwrap();
+        let gpu_clamp = GpuTensor::matrix(&backend, &a, usage).unwrap();
+        let gpu_lerp = GpuTensor::matrix(&backend, &a, usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        for (variant, gpu_out) in variants.iter().zip(gpu_masks.iter()) {
+            elementwise
+                .launch_compare(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    *variant,
+                    gpu_out,
+                    &gpu_a,
+                    &gpu_b,
+                )
+                .unwrap();
+        }
+        elementwise
+            .launch_where(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &gpu_where,
+                &gpu_mask,
+                &gpu_a,
+                &gpu_c,
+            )
+            .unwrap();
+        elementwise
+            .launch_clamp(&backend, &mut shapes, &mut pass, &gpu_clamp, 1.0, 3.0)
+            .unwrap();
+        elementwise
+            .launch_lerp(&backend, &mut shapes, &mut pass, &gpu_lerp, &gpu_c, 0.25)
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+
+        for (variant, gpu_out) in variants.iter().zip(gpu_masks.iter()) {
+            println!("Testing: {:?}", variant);
+            let mut result = DMatrix::<u32>::zeros(NROWS, NCOLS);
+            backend
+                .slow_read_buffer(gpu_out.buffer(), result.as_mut_slice())
+                .await
+                .unwrap();
+            let cpu_result = a.zip_map(&b, |a, b| variant.compare(a, b) as u32);
+            assert_eq!(result, cpu_result);
+        }
+
+        let mut result_where = zeros.clone();
+        let mut result_clamp = zeros.clone();
+        let mut result_lerp = zeros.clone();
+        backend
+            .slow_read_buffer(gpu_where.buffer(), result_where.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_clamp.buffer(), result_clamp.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_lerp.buffer(), result_lerp.as_mut_slice())
+            .await
+            .unwrap();
+
+        let cpu_where = DMatrix::from_fn(NROWS, NCOLS, |i, j| {
+            if mask[(i, j)] != 0 { a[(i, j)] } else { c[j] }
+        });
+        let cpu_clamp = a.map(|x| x.clamp(1.0, 3.0));
+        let cpu_lerp = DMatrix::from_fn(NROWS, NCOLS, |i, j| a[(i, j)] + 0.25 * (c[j] - a[(i, j)]));
+        assert_eq!(result_where, cpu_where);
+        assert_eq!(result_clamp, cpu_clamp);
+        assert_relative_eq!(result_lerp, cpu_lerp, epsilon = 1.0e-6);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorViewMut;
+use slang_hal::backend::{Backend, Buffer};
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// Slang module for initializing the content of a tensor directly on the gpu.
+///
+/// This avoids uploading large buffers from the host for tensors with a simple initial value
+/// (zeros, identity matrices, ramps, etc.)
+///
+/// The output is bound from the start of its buffer, and its offset is passed to the kernel, so
+/// it can be any view regardless of the storage buffer offset alignment required by the backend.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::fill")]
+pub struct Fill<B: Backend> {
+    /// Kernel for setting every element of a tensor to the same value.
+    pub fill: GpuFunction<B>,
+    /// Kernel for setting every matrix of a tensor to the identity.
+    pub eye: GpuFunction<B>,
+    /// Kernel for setting the elements of a tensor to evenly spaced values.
+    pub arange: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct FillParams {
+    start: f32,
+    step: f32,
+    offset: u32,
+    padding: u32,
+}
+
+#[derive(ShaderArgs)]
+struct FillArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape: &'a B::Buffer<ViewShape>,
+    out: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> Fill<B> {
+    /// Launches the kernel setting every element of `out` to `value`.
+    pub fn launch_fill<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorViewMut<'a, f32, B>>,
+        value: f32,
+    ) -> Result<(), B::Error> {
+        self.launch_generic(backend, shapes, pass, &self.fill, out.into(), value, 0.0)
+    }
+
+    /// Launches the kernel setting every element of `out` to zero.
+    pub fn launch_zeros<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorViewMut<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        self.launch_fill(backend, shapes, pass, out, 0.0)
+    }
+
+    /// Launches the kernel setting every element of `out` to one.
+    pub fn launch_ones<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorViewMut<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        self.launch_fill(backend, shapes, pass, out, 1.0)
+    }
+
+    /// Launches the kernel setting every matrix of `out` to the identity matrix.
+    ///
+    /// If the matrices aren’t square, only the elements with equal row and column indices are set
+    /// to one.
+    pub fn launch_eye<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorViewMut<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        self.launch_generic(backend, shapes, pass, &self.eye, out.into(), 0.0, 0.0)
+    }
+
+    /// Launches the kernel setting the `t`-th element of `out` to `start + step * t`.
+    ///
+    /// The elements are numbered in column-major order: rows first, then columns, matrices, and
+    /// cubes. This is independent from the memory layout of `out`.
+    pub fn launch_arange<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorViewMut<'a, f32, B>>,
+        start: f32,
+        step: f32,
+    ) -> Result<(), B::Error> {
+        self.launch_generic(backend, shapes, pass, &self.arange, out.into(), start, step)
+    }
+
+    /// Launches the kernel setting the elements of `out` to evenly spaced values, starting with
+    /// `start` and ending with `end`.
+    ///
+    /// The elements are numbered the same way as for [`Self::launch_arange`].
+    pub fn launch_linspace<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorViewMut<'a, f32, B>>,
+        start: f32,
+        end: f32,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let num_intervals = out.len().saturating_sub(1).max(1);
+        let step = (end - start) / num_intervals as f32;
+        self.launch_arange(backend, shapes, pass, out, start, step)
+    }
+
+    fn launch_generic(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        out: GpuTensorViewMut<f32, B>,
+        start: f32,
+        step: f32,
+    ) -> Result<(), B::Error> {
+        let out = out.as_ref();
+        let shape = out.shape();
+        let params = FillParams {
+            start,
+            step,
+            offset: out.offset(),
+            padding: 0,
+        };
+        shapes.insert(backend, shape)?;
+        shapes.insert_params(backend, params)?;
+
+        let args = FillArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape: shapes.get(shape).unwrap_or_else(|| unreachable!()),
+            out: out.raw_buffer().slice(0..),
+        };
+        function.launch_capped(backend, pass, &args, shape.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::{GpuTensor, TensorBuilder};
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_fill_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_fill_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_fill_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_fill_generic(backend).await;
+    }
+
+    async fn gpu_fill_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let fill = super::Fill::from_backend(&backend, &compiler).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+
+        const NROWS: u32 = 13;
+        const NCOLS: u32 = 7;
+        const NMATS: u32 = 3;
+        const LEN: u32 = 1001;
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let mut filled = GpuTensor::matrix_uninit(&backend, NROWS, NCOLS, usage).unwrap();
+        let mut eye = TensorBuilder::tensor([NROWS, NROWS, NMATS, 1], usage)
+            .build_uninit(&backend)
+            .unwrap();
+        let mut arange = GpuTensor::vector_uninit(&backend, LEN, usage).unwrap();
+        let mut linspace = GpuTensor::vector_uninit(&backend, LEN, usage).unwrap();
+        // Only initialize a sub-matrix to check non-contiguous views, starting at an offset that
+        // isn’t a multiple of the storage buffer offset alignment.
+        let mut zeros = GpuTensor::matrix(&backend, &DMatrix::repeat(7, 5, 1.0), usage).unwrap();
+
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        fill.launch_fill(&backend, &mut shapes, &mut pass, &mut filled, 4.2)
+            .unwrap();
+        fill.launch_eye(&backend, &mut shapes, &mut pass, &mut eye)
+            .unwrap();
+        fill.launch_arange(&backend, &mut shapes, &mut pass, &mut arange, -10.0, 0.5)
+            .unwrap();
+        fill.launch_linspace(&backend, &mut shapes, &mut pass, &mut linspace, 1.0, 3.0)
+            .unwrap();
+        fill.launch_zeros(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            zeros.as_view_mut().view(8, [4, 3], [None, Some(7)]),
+        )
+        .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+
+        let mut gpu_filled = DMatrix::zeros(NROWS as usize, NCOLS as usize);
+        let mut gpu_eye = vec![0.0; (NROWS * NROWS * NMATS) as usize];
+        let mut gpu_arange = DVector::zeros(LEN as usize);
+        let mut gpu_linspace = DVector::zeros(LEN as usize);
+        let mut gpu_zeros = DMatrix::zeros(7, 5);
+        backend
+            .slow_read_buffer(filled.buffer(), gpu_filled.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(eye.buffer(), &mut gpu_eye)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(arange.buffer(), gpu_arange.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(linspace.buffer(), gpu_linspace.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(zeros.buffer(), gpu_zeros.as_mut_slice())
+            .await
+            .unwrap();
+
+        let cpu_eye = DMatrix::<f32>::identity(NROWS as usize, NROWS as usize);
+        let cpu_arange = DVector::from_fn(LEN as usize, |i, _| -10.0 + 0.5 * i as f32);
+        let cpu_linspace =
+            DVector::from_fn(LEN as usize, |i, _| 1.0 + 2.0 * i as f32 / (LEN - 1) as f32);
+        // The view starts at the element 8 of the 7x5 matrix, i.e., at row 1 of column 1.
+        let mut cpu_zeros = DMatrix::repeat(7, 5, 1.0);
+        cpu_zeros.view_mut((1, 1), (4, 3)).fill(0.0);
+
+        assert!(gpu_filled == DMatrix::repeat(NROWS as usize, NCOLS as usize, 4.2));
+        for mat in gpu_eye.chunks_exact((NROWS * NROWS) as usize) {
+            assert!(mat == cpu_eye.as_slice());
+        }
+        approx::assert_relative_eq!(gpu_arange, cpu_arange, epsilon = 1.0e-5);
+        approx::assert_relative_eq!(gpu_linspace, cpu_linspace, epsilon = 1.0e-5);
+        assert!(gpu_zeros == cpu_zeros);
+    }
+}
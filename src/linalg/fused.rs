@@ -0,0 +1,701 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::ShaderArgs;
+use slang_hal::backend::{Backend, Buffer};
+use slang_hal::function::GpuFunction;
+use std::fmt::Write;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::path::Path;
+
+#[cfg(feature = "runtime")]
+use include_dir::{Dir, DirEntry, File};
+#[cfg(feature = "runtime")]
+use slang_hal::{Shader, SlangCompiler};
+#[cfg(feature = "runtime")]
+use std::collections::HashMap;
+#[cfg(not(feature = "runtime"))]
+use std::marker::PhantomData;
+#[cfg(feature = "runtime")]
+use std::sync::{Mutex, OnceLock};
+
+/// The maximum number of input tensors a [`FusedExpr`] can read from.
+pub const FUSED_MAX_INPUTS: usize = 8;
+
+/// A unary operation of a [`FusedExpr`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum FusedUnaryOp {
+    /// Negation: `-x`
+    Neg,
+    /// Absolute value: `|x|`
+    Abs,
+    /// Exponential: `exp(x)`
+    Exp,
+    /// Natural logarithm: `ln(x)`
+    Log,
+    /// Square root: `sqrt(x)`
+    Sqrt,
+    /// Hyperbolic tangent: `tanh(x)`
+    Tanh,
+    /// Logistic sigmoid: `1 / (1 + exp(-x))`
+    Sigmoid,
+    /// Rectified linear unit: `max(x, 0)`
+    Relu,
+    /// Sign: `-1`, `0`, or `1` depending on the sign of `x`
+    Sign,
+}
+
+/// A binary operation of a [`FusedExpr`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum FusedBinaryOp {
+    /// Sum: `a + b`
+    Add,
+    /// Subtraction: `a - b`
+    Sub,
+    /// Product: `a * b`
+    Mul,
+    /// Division: `a / b`
+    Div,
+    /// Minimum: `min(a, b)`
+    Min,
+    /// Maximum: `max(a, b)`
+    Max,
+    /// Power: `a^b`
+    Pow,
+}
+
+/// An element-wise expression evaluated by a single fused kernel.
+///
+/// Expressions are built from inputs, constants, and the usual arithmetic operators:
+///
+/// ```ignore
+/// let [a, b, c, d] = [0, 1, 2, 3].map(FusedExpr::input);
+/// let expr = (a * b + c).relu() / d;
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum FusedExpr {
+    /// The `i`-th input tensor.
+    Input(usize),
+    /// A constant, stored as the bits of an `f32` so the expression can be hashed.
+    Constant(u32),
+    /// A unary operation.
+    Unary(FusedUnaryOp, Box<FusedExpr>),
+    /// A binary operation.
+    Binary(FusedBinaryOp, Box<FusedExpr>, Box<FusedExpr>),
+}
+
+impl FusedExpr {
+    /// The expression reading the `i`-th input tensor.
+    pub fn input(i: usize) -> Self {
+        assert!(
+            i < FUSED_MAX_INPUTS,
+            "FusedExpr: the input index must be smaller than {FUSED_MAX_INPUTS}."
+        );
+        Self::Input(i)
+    }
+
+    /// The constant expression `value`.
+    pub fn constant(value: f32) -> Self {
+        Self::Constant(value.to_bits())
+    }
+
+    /// Applies the unary operation `op` to this expression.
+    pub fn unary(self, op: FusedUnaryOp) -> Self {
+        Self::Unary(op, Box::new(self))
+    }
+
+    /// Applies the binary operation `op` to this expression and `rhs`.
+    pub fn binary(self, op: FusedBinaryOp, rhs: Self) -> Self {
+        Self::Binary(op, Box::new(self), Box::new(rhs))
+    }
+
+    /// The absolute value of this expression.
+    pub fn abs(self) -> Self {
+        self.unary(FusedUnaryOp::Abs)
+    }
+
+    /// The exponential of this expression.
+    pub fn exp(self) -> Self {
+        self.unary(FusedUnaryOp::Exp)
+    }
+
+    /// The natural logarithm of this expression.
+    pub fn ln(self) -> Self {
+        self.unary(FusedUnaryOp::Log)
+    }
+
+    /// The square root of this expression.
+    pub fn sqrt(self) -> Self {
+        self.unary(FusedUnaryOp::Sqrt)
+    }
+
+    /// The hyperbolic tangent of this expression.
+    pub fn tanh(self) -> Self {
+        self.unary(FusedUnaryOp::Tanh)
+    }
+
+    /// The logistic sigmoid of this expression.
+    pub fn sigmoid(self) -> Self {
+        self.unary(FusedUnaryOp::Sigmoid)
+    }
+
+    /// The rectified linear unit of this expression.
+    pub fn relu(self) -> Self {
+        self.unary(FusedUnaryOp::Relu)
+    }
+
+    /// The sign of this expression, zero when the expression is zero.
+    pub fn sign(self) -> Self {
+        self.unary(FusedUnaryOp::Sign)
+    }
+
+    /// The element-wise minimum of this expression and `rhs`.
+    pub fn min(self, rhs: Self) -> Self {
+        self.binary(FusedBinaryOp::Min, rhs)
+    }
+
+    /// The element-wise maximum of this expression and `rhs`.
+    pub fn max(self, rhs: Self) -> Self {
+        self.binary(FusedBinaryOp::Max, rhs)
+    }
+
+    /// This expression raised to the power `rhs`.
+    pub fn pow(self, rhs: Self) -> Self {
+        self.binary(FusedBinaryOp::Pow, rhs)
+    }
+
+    /// The number of input tensors this expression reads from, i.e., one plus the largest input
+    /// index.
+    pub fn num_inputs(&self) -> usize {
+        match self {
+            Self::Input(i) => i + 1,
+            Self::Constant(_) => 0,
+            Self::Unary(_, x) => x.num_inputs(),
+            Self::Binary(_, a, b) => a.num_inputs().max(b.num_inputs()),
+        }
+    }
+
+    /// Evaluates this expression on the CPU.
+    pub fn eval(&self, inputs: &[f32]) -> f32 {
+        match self {
+            Self::Input(i) => inputs[*i],
+            Self::Constant(bits) => f32::from_bits(*bits),
+            Self::Unary(op, x) => {
+                let x = x.eval(inputs);
+                match op {
+                    FusedUnaryOp::Neg => -x,
+                    FusedUnaryOp::Abs => x.abs(),
+                    FusedUnaryOp::Exp => x.exp(),
+                    FusedUnaryOp::Log => x.ln(),
+                    FusedUnaryOp::Sqrt => x.sqrt(),
+                    FusedUnaryOp::Tanh => x.tanh(),
+                    FusedUnaryOp::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+                    FusedUnaryOp::Relu => x.max(0.0),
+                    FusedUnaryOp::Sign if x == 0.0 => 0.0,
+                    FusedUnaryOp::Sign => x.signum(),
+                }
+            }
+            Self::Binary(op, a, b) => {
+                let (a, b) = (a.eval(inputs), b.eval(inputs));
+                match op {
+                    FusedBinaryOp::Add => a + b,
+                    FusedBinaryOp::Sub => a - b,
+                    FusedBinaryOp::Mul => a * b,
+                    FusedBinaryOp::Div => a / b,
+                    FusedBinaryOp::Min => a.min(b),
+                    FusedBinaryOp::Max => a.max(b),
+                    FusedBinaryOp::Pow => a.powf(b),
+                }
+            }
+        }
+    }
+
+    fn write_slang(&self, out: &mut String) {
+        match self {
+            Self::Input(i) => write!(out, "x{i}").unwrap(),
+            // NOTE: the constant is written as its bits so it is exact, even for infinities.
+            Self::Constant(bits) => write!(out, "asfloat({bits}u)").unwrap(),
+            Self::Unary(op, x) => {
+                let (prefix, suffix) = match op {
+                    FusedUnaryOp::Neg => ("(-", ")"),
+                    FusedUnaryOp::Abs => ("abs(", ")"),
+                    FusedUnaryOp::Exp => ("exp(", ")"),
+                    FusedUnaryOp::Log => ("log(", ")"),
+                    FusedUnaryOp::Sqrt => ("sqrt(", ")"),
+                    FusedUnaryOp::Tanh => ("tanh(", ")"),
+                    FusedUnaryOp::Sigmoid => ("(1.0 / (1.0 + exp(-", ")))"),
+                    FusedUnaryOp::Relu => ("max(0.0, ", ")"),
+                    FusedUnaryOp::Sign => ("sign(", ")"),
+                };
+                out.push_str(prefix);
+                x.write_slang(out);
+                out.push_str(suffix);
+            }
+            Self::Binary(op, a, b) => {
+                let (prefix, infix) = match op {
+                    FusedBinaryOp::Add => ("(", " + "),
+                    FusedBinaryOp::Sub => ("(", " - "),
+                    FusedBinaryOp::Mul => ("(", " * "),
+                    FusedBinaryOp::Div => ("(", " / "),
+                    FusedBinaryOp::Min => ("min(", ", "),
+                    FusedBinaryOp::Max => ("max(", ", "),
+                    FusedBinaryOp::Pow => ("pow(", ", "),
+                };
+                out.push_str(prefix);
+                a.write_slang(out);
+                out.push_str(infix);
+                b.write_slang(out);
+                out.push(')');
+            }
+        }
+    }
+
+    /// Generates the source of the Slang module evaluating this expression.
+    ///
+    /// The module declares a single entry point named `fused`. See
+    /// [`Self::write_slang_module`] for compiling it ahead of time.
+    pub fn slang_module(&self) -> String {
+        let num_inputs = self.num_inputs();
+        let mut src = String::new();
+        src.push_str(
+            "import stensor.linalg.shape;
+import stensor.utils.limits;
+
+static const int WORKGROUP_SIZE = 256;
+static const uint MAX_NUM_THREADS = MAX_NUM_WORKGROUPS * WORKGROUP_SIZE;
+
+struct FusedParams {
+    // The index, in `out`, of the first element of the output view (`.x`).
+    uint4 out_offset;
+    // The index, in `input_{i}`, of the first element of the `i`-th input view.
+    uint4 input_offsets[2];
+}
+
+// NOTE: the inputs are bound as read-write so they can alias `out`.
+// NOTE: all the tensors are bound from the start of their buffer, and their offsets are given
+//       by `params`, so they don’t need to match the storage buffer offset alignment.
+[shader(\"compute\")]
+[numthreads(WORKGROUP_SIZE, 1, 1)]
+void fused(
+    uint3 invocation_id: SV_DispatchThreadID,
+    ConstantBuffer<FusedParams> params,
+    ConstantBuffer<Shape> shape_out,
+",
+        );
+        for i in 0..num_inputs {
+            writeln!(src, "    ConstantBuffer<Shape> shape_{i},").unwrap();
+        }
+        src.push_str("    RWStructuredBuffer<float> out,\n");
+        for i in 0..num_inputs {
+            writeln!(src, "    RWStructuredBuffer<float> input_{i},").unwrap();
+        }
+        src.push_str(
+            ") {
+    for (var thread_id = invocation_id.x; thread_id < shape_out.len(); thread_id += MAX_NUM_THREADS) {
+        let id = shape_out.decompose(thread_id);
+",
+        );
+        for i in 0..num_inputs {
+            let offset = format!("params.input_offsets[{}][{}]", i / 4, i % 4);
+            writeln!(
+                src,
+                "        let x{i} = input_{i}[{offset} + shape_{i}.it_wrapping(id)];"
+            )
+            .unwrap();
+        }
+        src.push_str("        out[params.out_offset.x + shape_out.it(id)] = ");
+        self.write_slang(&mut src);
+        src.push_str(
+            ";
+    }
+}
+",
+        );
+        src
+    }
+
+    /// Writes the Slang module evaluating this expression (see [`Self::slang_module`]) to the
+    /// file `path`, creating its parent directories if needed.
+    ///
+    /// This is meant to be called from a build script for compiling the kernel ahead of time
+    /// with the `comptime` feature. The file must be written into the shader directory compiled
+    /// by the build script, before it is compiled:
+    ///
+    /// ```ignore
+    /// // build.rs
+    /// let [a, b] = [0, 1].map(FusedExpr::input);
+    /// (a * b).relu()
+    ///     .write_slang_module("shaders/my_crate/mul_relu.slang")
+    ///     .unwrap();
+    /// let mut compiler = ShaderCompiler::new(vec![], &out_dir);
+    /// compiler.add_dir(stensor::SLANG_SRC_DIR);
+    /// compiler.compile_shaders_dir("shaders", &[]).unwrap();
+    ///
+    /// // lib.rs
+    /// #[derive(Shader)]
+    /// #[shader(module = "my_crate::mul_relu")]
+    /// struct MulRelu<B: Backend> {
+    ///     fused: GpuFunction<B>,
+    /// }
+    /// ```
+    ///
+    /// The kernel is then launched with [`FusedElementwise::launch_precompiled`].
+    pub fn write_slang_module(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.slang_module())
+    }
+}
+
+impl Add for FusedExpr {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        self.binary(FusedBinaryOp::Add, rhs)
+    }
+}
+
+impl Sub for FusedExpr {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self.binary(FusedBinaryOp::Sub, rhs)
+    }
+}
+
+impl Mul for FusedExpr {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        self.binary(FusedBinaryOp::Mul, rhs)
+    }
+}
+
+impl Div for FusedExpr {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        self.binary(FusedBinaryOp::Div, rhs)
+    }
+}
+
+impl Neg for FusedExpr {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.unary(FusedUnaryOp::Neg)
+    }
+}
+
+/// The kernel generated from a [`FusedExpr`] and compiled at runtime.
+#[cfg(feature = "runtime")]
+#[derive(Shader)]
+#[shader(module = "stensor_fused::fused")]
+struct FusedKernel<B: Backend> {
+    fused: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct FusedParams {
+    out_offset: [u32; 4],
+    input_offsets: [u32; FUSED_MAX_INPUTS],
+}
+
+#[derive(ShaderArgs)]
+struct FusedArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_out: &'a B::Buffer<ViewShape>,
+    shape_0: Option<&'a B::Buffer<ViewShape>>,
+    shape_1: Option<&'a B::Buffer<ViewShape>>,
+    shape_2: Option<&'a B::Buffer<ViewShape>>,
+    shape_3: Option<&'a B::Buffer<ViewShape>>,
+    shape_4: Option<&'a B::Buffer<ViewShape>>,
+    shape_5: Option<&'a B::Buffer<ViewShape>>,
+    shape_6: Option<&'a B::Buffer<ViewShape>>,
+    shape_7: Option<&'a B::Buffer<ViewShape>>,
+    out: B::BufferSlice<'a, f32>,
+    input_0: Option<B::BufferSlice<'a, f32>>,
+    input_1: Option<B::BufferSlice<'a, f32>>,
+    input_2: Option<B::BufferSlice<'a, f32>>,
+    input_3: Option<B::BufferSlice<'a, f32>>,
+    input_4: Option<B::BufferSlice<'a, f32>>,
+    input_5: Option<B::BufferSlice<'a, f32>>,
+    input_6: Option<B::BufferSlice<'a, f32>>,
+    input_7: Option<B::BufferSlice<'a, f32>>,
+}
+
+/// Launcher of fused element-wise kernels generated from [`FusedExpr`]s.
+///
+/// Evaluating an expression like `relu(a * b + c) / d` with [`OpAssign`](crate::OpAssign)
+/// costs one dispatch (and one round trip to memory) per operator. Here, one kernel is generated
+/// for each distinct expression. With the `runtime` feature, it is compiled the first time the
+/// expression is launched with [`Self::launch`], and cached for subsequent launches. Otherwise,
+/// the kernels must be generated ahead of time with [`FusedExpr::write_slang_module`] and
+/// launched with [`Self::launch_precompiled`].
+///
+/// The tensors are bound from the start of their buffers, and their offsets are passed to the
+/// kernel, so views can start at any element regardless of the storage buffer offset alignment
+/// required by the backend.
+pub struct FusedElementwise<B: Backend> {
+    #[cfg(feature = "runtime")]
+    kernels: HashMap<FusedExpr, FusedKernel<B>>,
+    #[cfg(not(feature = "runtime"))]
+    _phantom: PhantomData<B>,
+}
+
+impl<B: Backend> Default for FusedElementwise<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Backend> FusedElementwise<B> {
+    /// Creates a launcher with an empty kernel cache.
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "runtime")]
+            kernels: HashMap::new(),
+            #[cfg(not(feature = "runtime"))]
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The number of kernels compiled so far.
+    #[cfg(feature = "runtime")]
+    pub fn num_kernels(&self) -> usize {
+        self.kernels.len()
+    }
+
+    /// Launches the kernel computing `out = expr(inputs)`, compiling it first if this expression
+    /// wasn’t launched before.
+    ///
+    /// The `i`-th input of the expression reads `inputs[i]`. The shape of `out` must be a
+    /// multiple of the shape of each input, which is repeated to match it (like
+    /// [`OpAssign::launch`](crate::OpAssign::launch)). `out` may alias any of the inputs.
+    #[cfg(feature = "runtime")]
+    pub fn launch<'a>(
+        &mut self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        expr: &FusedExpr,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        inputs: &[GpuTensorView<'a, f32, B>],
+    ) -> Result<(), B::Error> {
+        assert!(
+            inputs.len() >= expr.num_inputs(),
+            "FusedElementwise: the expression reads {} inputs.",
+            expr.num_inputs()
+        );
+
+        if !self.kernels.contains_key(expr) {
+            let kernel = Self::compile(backend, expr)?;
+            self.kernels.insert(expr.clone(), kernel);
+        }
+
+        let kernel = &self.kernels[expr];
+        Self::launch_precompiled(backend, shapes, pass, &kernel.fused, out, inputs)
+    }
+
+    /// Launches the kernel `function`, generated from [`FusedExpr::slang_module`] and compiled
+    /// ahead of time, computing `out = expr(inputs)`.
+    ///
+    /// See [`Self::launch`] for details on the arguments. The module must be generated by the same
+    /// version of this crate since the kernel signature may change between versions.
+    pub fn launch_precompiled<'a>(
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        inputs: &[GpuTensorView<'a, f32, B>],
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        assert!(
+            inputs.len() <= FUSED_MAX_INPUTS,
+            "FusedElementwise: at most {FUSED_MAX_INPUTS} inputs are supported."
+        );
+
+        let shape_out = out.shape();
+        shapes.insert(backend, shape_out)?;
+        for input in inputs {
+            assert!(
+                shape_out.is_multiple_of(input.shape()),
+                "FusedElementwise: the output shape {:?} must be a multiple of {:?}.",
+                shape_out.size,
+                input.shape().size
+            );
+            shapes.insert(backend, input.shape())?;
+        }
+
+        let mut params = FusedParams {
+            out_offset: [out.offset(), 0, 0, 0],
+            input_offsets: [0; FUSED_MAX_INPUTS],
+        };
+        for (offset, input) in params.input_offsets.iter_mut().zip(inputs) {
+            *offset = input.offset();
+        }
+        shapes.insert_params(backend, params)?;
+
+        let shape = |i: usize| {
+            inputs
+                .get(i)
+                .map(|input| shapes.get(input.shape()).unwrap_or_else(|| unreachable!()))
+        };
+        let input = |i: usize| inputs.get(i).map(|input| input.raw_buffer().slice(0..));
+        let args = FusedArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+            shape_0: shape(0),
+            shape_1: shape(1),
+            shape_2: shape(2),
+            shape_3: shape(3),
+            shape_4: shape(4),
+            shape_5: shape(5),
+            shape_6: shape(6),
+            shape_7: shape(7),
+            out: out.raw_buffer().slice(0..),
+            input_0: input(0),
+            input_1: input(1),
+            input_2: input(2),
+            input_3: input(3),
+            input_4: input(4),
+            input_5: input(5),
+            input_6: input(6),
+            input_7: input(7),
+        };
+        function.launch_capped(backend, pass, &args, out.len() as u32)
+    }
+
+    #[cfg(feature = "runtime")]
+    fn compile(backend: &B, expr: &FusedExpr) -> Result<FusedKernel<B>, B::Error> {
+        let root = Self::module_dir(expr);
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        compiler.add_dir(Dir::new("", root));
+        FusedKernel::from_backend(backend, &compiler)
+    }
+
+    /// The virtual shader directory containing the module generated for `expr`.
+    ///
+    /// The module must outlive the compiler, so it is leaked. The leaked directories are shared
+    /// by all the launchers and keyed by expression, so the leak is bounded by the number of
+    /// distinct expressions compiled by the process.
+    #[cfg(feature = "runtime")]
+    fn module_dir(expr: &FusedExpr) -> &'static [DirEntry<'static>] {
+        type ModuleDirs = Mutex<HashMap<FusedExpr, &'static [DirEntry<'static>]>>;
+        static MODULE_DIRS: OnceLock<ModuleDirs> = OnceLock::new();
+
+        let mut dirs = MODULE_DIRS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *dirs.entry(expr.clone()).or_insert_with(|| {
+            let src: &'static str = expr.slang_module().leak();
+            let file = File::new("stensor_fused/fused.slang", src.as_bytes());
+            let files: &'static [DirEntry<'static>] = Box::leak(Box::new([DirEntry::File(file)]));
+            let dir = Dir::new("stensor_fused", files);
+            Box::leak(Box::new([DirEntry::Dir(dir)]))
+        })
+    }
+}
+
+#[cfg(all(test, feature = "runtime"))]
+mod test {
+    use super::{FusedElementwise, FusedExpr};
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use approx::assert_relative_eq;
+    use nalgebra::DMatrix;
+    use slang_hal::BufferUsages;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_fused_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_fused_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_fused_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_fused_generic(backend).await;
+    }
+
+    async fn gpu_fused_generic(backend: impl Backend) {
+        let mut fused = FusedElementwise::new();
+
+        const NROWS: usize = 301;
+        const NCOLS: usize = 37;
+
+        let [a, b, c, d] = [0, 1, 2, 3].map(FusedExpr::input);
+        let expr = (a * b + c).relu() / d;
+        let [x, y] = [0, 1].map(FusedExpr::input);
+        let expr_inplace = (x.sigmoid() - FusedExpr::constant(0.5)).max(y.tanh());
+
+        let ma = DMatrix::<f32>::new_random(NROWS, NCOLS).add_scalar(-0.5);
+        let mb = DMatrix::<f32>::new_random(NROWS, NCOLS);
+        // Broadcast along the rows.
+        let mc = DMatrix::<f32>::new_random(1, NCOLS).add_scalar(-0.5);
+        // Broadcast along the columns.
+        let md = DMatrix::<f32>::new_random(NROWS, 1).add_scalar(1.0);
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let gpu_a = GpuTensor::matrix(&backend, &ma, usage).unwrap();
+        let gpu_b = GpuTensor::matrix(&backend, &mb, usage).unwrap();
+        let gpu_c = GpuTensor::matrix(&backend, &mc, usage).unwrap();
+        let gpu_d = GpuTensor::matrix(&backend, &md, usage).unwrap();
+        let gpu_out = GpuTensor::matrix(&backend, &DMatrix::zeros(NROWS, NCOLS), usage).unwrap();
+        let gpu_inplace = GpuTensor::matrix(&backend, &ma, usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        let inputs = [&gpu_a, &gpu_b, &gpu_c, &gpu_d].map(|t| t.as_view());
+        // Launch twice to exercise the cache.
+        for _ in 0..2 {
+            fused
+                .launch(&backend, &mut shapes, &mut pass, &expr, &gpu_out, &inputs)
+                .unwrap();
+        }
+        // Skip the first row so the views don’t start at an aligned offset.
+        let inplace = gpu_inplace.rows(1, NROWS as u32 - 1);
+        let inputs_inplace = [inplace, gpu_b.rows(1, NROWS as u32 - 1)];
+        fused
+            .launch(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                &expr_inplace,
+                inplace,
+                &inputs_inplace,
+            )
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+        assert_eq!(fused.num_kernels(), 2);
+
+        let mut result = DMatrix::zeros(NROWS, NCOLS);
+        let mut result_inplace = DMatrix::zeros(NROWS, NCOLS);
+        backend
+            .slow_read_buffer(gpu_out.buffer(), result.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_inplace.buffer(), result_inplace.as_mut_slice())
+            .await
+            .unwrap();
+
+        let cpu_result = DMatrix::from_fn(NROWS, NCOLS, |i, j| {
+            expr.eval(&[ma[(i, j)], mb[(i, j)], mc[j], md[i]])
+        });
+        let mut cpu_result_inplace = ma.zip_map(&mb, |x, y| expr_inplace.eval(&[x, y]));
+        cpu_result_inplace.row_mut(0).copy_from(&ma.row(0));
+        assert_relative_eq!(result, cpu_result, epsilon = 1.0e-5);
+        assert_relative_eq!(result_inplace, cpu_result_inplace, epsilon = 1.0e-5);
+    }
+}
@@ -0,0 +1,389 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// Slang module for index-driven data movement (index selection, gather, and scatter).
+///
+/// All the kernels take a `u32` index tensor. Indices that are out of bounds of the indexed
+/// dimension are ignored: the corresponding output element is set to zero for
+/// [`Gather::launch_index_select`] and [`Gather::launch_gather`], and left untouched for
+/// [`Gather::launch_scatter`] and [`Gather::launch_scatter_add`].
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::gather")]
+pub struct Gather<B: Backend> {
+    /// Kernel for selecting slices of a tensor along an axis from a vector of indices.
+    pub index_select: GpuFunction<B>,
+    /// Kernel for gathering elements of a tensor along an axis from a tensor of indices.
+    pub gather: GpuFunction<B>,
+    /// Kernel for scattering elements of a tensor along an axis from a tensor of indices.
+    pub scatter: GpuFunction<B>,
+    /// Kernel for accumulating elements of a tensor along an axis from a tensor of indices.
+    pub scatter_add: GpuFunction<B>,
+}
+
+#[derive(ShaderArgs)]
+struct GatherArgs<'a, B: Backend> {
+    shape_out: &'a B::Buffer<ViewShape>,
+    shape_src: &'a B::Buffer<ViewShape>,
+    shape_indices: &'a B::Buffer<ViewShape>,
+    out: B::BufferSlice<'a, f32>,
+    src: B::BufferSlice<'a, f32>,
+    indices: B::BufferSlice<'a, u32>,
+}
+
+impl<B: Backend> Gather<B> {
+    /// Launches the kernel computing `out[.., j, ..] = src[.., indices[j], ..]` where `j` is
+    /// the index along the dimension `axis`.
+    ///
+    /// The `indices` tensor must be a vector with as many elements as the size of `out` along
+    /// `axis`. The other dimensions of `out` and `src` must match.
+    pub fn launch_index_select<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        src: impl Into<GpuTensorView<'a, f32, B>>,
+        indices: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let src = src.into();
+        let indices = indices.into();
+        let num_indices = indices.len() as u32;
+
+        assert!(axis < 4, "Invalid axis {axis}.");
+        assert_eq!(
+            out.size(axis),
+            num_indices,
+            "IndexSelect: the output size along the axis {axis} must match the number of indices."
+        );
+        for k in (0..4).filter(|k| *k != axis) {
+            assert_eq!(out.size(k), src.size(k), "IndexSelect: dimension mismatch.");
+        }
+
+        // Move the dimension of the index vector to the front.
+        let vector_dim = (0..4)
+            .find(|k| indices.size(*k) == num_indices)
+            .expect("IndexSelect: the indices must be a vector.");
+        let shape_indices = indices.shape().swap_dims(0, vector_dim);
+
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            &self.index_select,
+            axis,
+            (out, src, indices),
+            shape_indices,
+            out.len(),
+        )
+    }
+
+    /// Launches the kernel computing `out[.., i, ..] = src[.., indices[.., i, ..], ..]` where `i`
+    /// is the index along the dimension `axis`.
+    ///
+    /// The `indices` tensor must have the same size as `out`. The dimensions of `out` and `src`
+    /// must match, except along `axis`.
+    pub fn launch_gather<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        src: impl Into<GpuTensorView<'a, f32, B>>,
+        indices: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let src = src.into();
+        let indices = indices.into();
+        assert_eq!(
+            out.shape().size,
+            indices.shape().size,
+            "Gather: the indices and output shapes must match."
+        );
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            &self.gather,
+            axis,
+            (out, src, indices),
+            indices.shape().swap_dims(0, axis),
+            out.len(),
+        )
+    }
+
+    /// Launches the kernel computing `out[.., indices[.., i, ..], ..] = src[.., i, ..]` where `i`
+    /// is the index along the dimension `axis`.
+    ///
+    /// The `indices` tensor must have the same size as `src`. The dimensions of `out` and `src`
+    /// must match, except along `axis`. If several elements are scattered to the same location,
+    /// which one is written last is unspecified.
+    pub fn launch_scatter<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        src: impl Into<GpuTensorView<'a, f32, B>>,
+        indices: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        self.launch_scatter_generic(
+            backend,
+            shapes,
+            pass,
+            &self.scatter,
+            axis,
+            out.into(),
+            src.into(),
+            indices.into(),
+        )
+    }
+
+    /// Launches the kernel computing `out[.., indices[.., i, ..], ..] += src[.., i, ..]` where `i`
+    /// is the index along the dimension `axis`.
+    ///
+    /// The `indices` tensor must have the same size as `src`. The dimensions of `out` and `src`
+    /// must match, except along `axis`. Elements scattered to the same location are accumulated
+    /// atomically, in an unspecified order, so the result isn’t bitwise deterministic.
+    pub fn launch_scatter_add<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        src: impl Into<GpuTensorView<'a, f32, B>>,
+        indices: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        self.launch_scatter_generic(
+            backend,
+            shapes,
+            pass,
+            &self.scatter_add,
+            axis,
+            out.into(),
+            src.into(),
+            indices.into(),
+        )
+    }
+
+    fn launch_scatter_generic<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        axis: usize,
+        out: GpuTensorView<'a, f32, B>,
+        src: GpuTensorView<'a, f32, B>,
+        indices: GpuTensorView<'a, u32, B>,
+    ) -> Result<(), B::Error> {
+        assert_eq!(
+            src.shape().size,
+            indices.shape().size,
+            "Scatter: the indices and source shapes must match."
+        );
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            function,
+            axis,
+            (out, src, indices),
+            indices.shape().swap_dims(0, axis),
+            src.len(),
+        )
+    }
+
+    fn launch_generic<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        axis: usize,
+        (out, src, indices): (
+            GpuTensorView<'a, f32, B>,
+            GpuTensorView<'a, f32, B>,
+            GpuTensorView<'a, u32, B>,
+        ),
+        shape_indices: ViewShape,
+        num_threads: u64,
+    ) -> Result<(), B::Error> {
+        assert!(axis < 4, "Invalid axis {axis}.");
+        for k in (0..4).filter(|k| *k != axis) {
+            assert_eq!(out.size(k), src.size(k), "Gather: dimension mismatch.");
+        }
+
+        // The kernels always index the first dimension.
+        let shape_out = out.shape().swap_dims(0, axis);
+        let shape_src = src.shape().swap_dims(0, axis);
+
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_src)?;
+        shapes.insert(backend, shape_indices)?;
+
+        let args = GatherArgs {
+            shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+            shape_src: shapes.get(shape_src).unwrap_or_else(|| unreachable!()),
+            shape_indices: shapes.get(shape_indices).unwrap_or_else(|| unreachable!()),
+            out: out.buffer(),
+            src: src.buffer(),
+            indices: indices.buffer(),
+        };
+        function.launch_capped(backend, pass, &args, num_threads as u32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_gather_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_gather_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_gather_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_gather_generic(backend).await;
+    }
+
+    async fn gpu_gather_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let gather = super::Gather::from_backend(&backend, &compiler).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+
+        const NROWS: usize = 31;
+        const NCOLS: usize = 17;
+        const NSELECTED: usize = 40;
+
+        let src = DMatrix::<f32>::new_random(NROWS, NCOLS);
+        // Selected columns, with repetitions (e.g. embedding lookup).
+        let selected: Vec<u32> = (0..NSELECTED).map(|i| ((i * 7) % NCOLS) as u32).collect();
+        // Row indices for gather and scatter.
+        // Each column of `row_ids` is a permutation, so the scatter has no conflicts.
+        let row_ids = DMatrix::<u32>::from_fn(NROWS, NCOLS, |i, j| ((i * 3 + j) % NROWS) as u32);
+        // Row indices with repetitions, for the accumulating scatter.
+        let dup_row_ids =
+            DMatrix::<u32>::from_fn(NROWS, NCOLS, |i, j| ((i / 3 + j) % NROWS) as u32);
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let gpu_src = GpuTensor::matrix(&backend, &src, BufferUsages::STORAGE).unwrap();
+        let gpu_selected = GpuTensor::vector(&backend, &selected, BufferUsages::STORAGE).unwrap();
+        let gpu_row_ids = GpuTensor::matrix(&backend, &row_ids, BufferUsages::STORAGE).unwrap();
+        let gpu_dup_row_ids =
+            GpuTensor::matrix(&backend, &dup_row_ids, BufferUsages::STORAGE).unwrap();
+
+        let mut gpu_index_select = DMatrix::<f32>::zeros(NROWS, NSELECTED);
+        let mut gpu_gather = DMatrix::<f32>::zeros(NROWS, NCOLS);
+        let mut gpu_scatter = DMatrix::<f32>::zeros(NROWS, NCOLS);
+        let mut gpu_scatter_add = DMatrix::<f32>::zeros(NROWS, NCOLS);
+        let index_select_out = GpuTensor::matrix(&backend, &gpu_index_select, usage).unwrap();
+        let gather_out = GpuTensor::matrix(&backend, &gpu_gather, usage).unwrap();
+        let scatter_out = GpuTensor::matrix(&backend, &gpu_scatter, usage).unwrap();
+        let scatter_add_out = GpuTensor::matrix(&backend, &gpu_scatter_add, usage).unwrap();
+
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        gather
+            .launch_index_select(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                1,
+                &index_select_out,
+                &gpu_src,
+                &gpu_selected,
+            )
+            .unwrap();
+        gather
+            .launch_gather(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                0,
+                &gather_out,
+                &gpu_src,
+                &gpu_row_ids,
+            )
+            .unwrap();
+        gather
+            .launch_scatter(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                0,
+                &scatter_out,
+                &gpu_src,
+                &gpu_row_ids,
+            )
+            .unwrap();
+        gather
+            .launch_scatter_add(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                0,
+                &scatter_add_out,
+                &gpu_src,
+                &gpu_dup_row_ids,
+            )
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+        backend
+            .slow_read_buffer(index_select_out.buffer(), gpu_index_select.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gather_out.buffer(), gpu_gather.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(scatter_out.buffer(), gpu_scatter.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(scatter_add_out.buffer(), gpu_scatter_add.as_mut_slice())
+            .await
+            .unwrap();
+
+        let selected_cols: Vec<_> = selected.iter().map(|j| *j as usize).collect();
+        let cpu_index_select = src.select_columns(&selected_cols);
+        let cpu_gather = DMatrix::from_fn(NROWS, NCOLS, |i, j| src[(row_ids[(i, j)] as usize, j)]);
+        let mut cpu_scatter = DMatrix::zeros(NROWS, NCOLS);
+        let mut cpu_scatter_add = DMatrix::zeros(NROWS, NCOLS);
+        for i in 0..NROWS {
+            for j in 0..NCOLS {
+                cpu_scatter[(row_ids[(i, j)] as usize, j)] = src[(i, j)];
+                cpu_scatter_add[(dup_row_ids[(i, j)] as usize, j)] += src[(i, j)];
+            }
+        }
+
+        assert!(gpu_index_select == cpu_index_select);
+        assert!(gpu_gather == cpu_gather);
+        assert!(gpu_scatter == cpu_scatter);
+        approx::assert_relative_eq!(gpu_scatter_add, cpu_scatter_add, epsilon = 1.0e-5);
+    }
+}
@@ -1,15 +1,58 @@
 //! Fundamental linear-algebra matrix/vector operations.
 
+#[cfg(feature = "runtime")]
+mod autodiff;
+mod block;
+mod compact;
+mod concat;
 mod contiguous;
+mod dense_cholesky;
+mod dense_lu;
+mod dense_qr;
+mod elementwise;
+mod fill;
+mod fused;
+mod gather;
 mod gemm;
 mod gemv;
+mod moments;
+mod norm;
 mod op_assign;
+mod random;
 mod reduce;
 mod repeat;
+mod scan;
+mod softmax;
+mod sort;
+mod trsm;
 
+/// The maximum number of workgroups dispatched along each dimension of a grid.
+///
+/// NOTE: this must match `MAX_NUM_WORKGROUPS` from `limits.slang`.
+pub(crate) const MAX_NUM_WORKGROUPS: u32 = 65535;
+
+#[cfg(feature = "runtime")]
+pub use autodiff::{Tape, TapeVar};
+pub use block::Block;
+pub use compact::{Compact, CompactValue};
+pub use concat::{CONCAT_MAX_INPUTS, Concat};
 pub use contiguous::Contiguous;
+pub use dense_cholesky::DenseCholesky;
+pub use dense_lu::DenseLu;
+pub use dense_qr::DenseQr;
+pub use elementwise::{CompareVariant, Elementwise};
+pub use fill::Fill;
+pub use fused::{FUSED_MAX_INPUTS, FusedBinaryOp, FusedElementwise, FusedExpr, FusedUnaryOp};
+pub use gather::Gather;
 pub use gemm::{Gemm, GemmVariant};
 pub use gemv::{Gemv, GemvVariant, MatrixMode, N, T};
-pub use op_assign::{BinOpOffsets, OpAssign, OpAssignVariant};
-pub use reduce::{Reduce, ReduceVariant};
+pub use moments::{MOMENTS_NUM_PARTIALS, MOMENTS_WORKSPACE_LEN, Moments, MomentsVariant};
+pub use norm::Norm;
+pub use op_assign::{BinOpArgs, BinOpOffsets, CustomBinOp, OpAssign, OpAssignVariant};
+pub use random::{Random, RandomDistribution, philox4x32_10};
+pub use reduce::{CustomReduction, Reduce, ReduceArgs, ReduceVariant};
 pub use repeat::Repeat;
+pub use scan::{Scan, ScanValue, ScanVariant};
+pub use softmax::{Softmax, SoftmaxVariant};
+pub use sort::{Sort, SortKey};
+pub use trsm::{Diagonal, Triangle, Trsm};
@@ -0,0 +1,418 @@
+use crate::linalg::MAX_NUM_WORKGROUPS;
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// The number of workgroups computing partial results for whole-tensor [`Moments`] reductions.
+pub const MOMENTS_NUM_PARTIALS: u32 = 256;
+/// The minimum number of elements of the workspace needed by [`Moments::launch`].
+pub const MOMENTS_WORKSPACE_LEN: u32 = MOMENTS_NUM_PARTIALS * 3;
+
+/// The desired statistic for the [`Moments`] kernel.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum MomentsVariant {
+    /// Mean: `result = (input[0] + input[1] + ...) / n`
+    Mean,
+    /// Variance: `result = ((input[0] - mean)² + (input[1] - mean)² + ...) / (n - correction)`
+    Variance,
+    /// Standard deviation: the square root of the variance.
+    Std,
+}
+
+impl MomentsVariant {
+    fn id(self) -> u32 {
+        match self {
+            Self::Mean => 0,
+            Self::Variance => 1,
+            Self::Std => 2,
+        }
+    }
+}
+
+/// Slang module for computing the mean, variance, or standard deviation of a tensor.
+///
+/// The statistics are accumulated with Welford’s algorithm, and partial results are combined
+/// with Chan et al.’s parallel algorithm. This is much more accurate than computing the variance
+/// from the sum of squares, especially for values far from zero.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::moments")]
+pub struct Moments<B: Backend> {
+    /// Kernel computing the partial statistics of a whole tensor, one per workgroup.
+    pub moments_partial: GpuFunction<B>,
+    /// Kernel combining the partial statistics computed by `moments_partial`.
+    pub moments_finalize: GpuFunction<B>,
+    /// Kernel computing the statistics along the first axis of a tensor.
+    pub moments_axis: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct MomentsParams {
+    variant: u32,
+    correction: f32,
+    padding: [u32; 2],
+}
+
+#[derive(ShaderArgs)]
+struct MomentsArgs<'a, B: Backend> {
+    params: Option<&'a B::Buffer<u32>>,
+    shape_out: Option<&'a B::Buffer<ViewShape>>,
+    shape_input: Option<&'a B::Buffer<ViewShape>>,
+    out: Option<B::BufferSlice<'a, f32>>,
+    input: Option<B::BufferSlice<'a, f32>>,
+    partials: Option<B::BufferSlice<'a, f32>>,
+}
+
+impl<B: Backend> Moments<B> {
+    /// Launches the kernels computing the statistic `variant` of every element of `input`.
+    ///
+    /// The result is written to the first element of `out`. The `workspace` must have at least
+    /// [`MOMENTS_WORKSPACE_LEN`] elements and is used to store partial results. The `correction`
+    /// is subtracted from the number of elements when computing the variance (use `0` for the
+    /// population variance and `1` for the sample variance).
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: MomentsVariant,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        input: impl Into<GpuTensorView<'a, f32, B>>,
+        workspace: impl Into<GpuTensorView<'a, f32, B>>,
+        correction: u32,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let input = input.into();
+        let workspace = workspace.into();
+        assert!(
+            workspace.len() >= MOMENTS_WORKSPACE_LEN as u64,
+            "Moments: the workspace must have at least {MOMENTS_WORKSPACE_LEN} elements."
+        );
+        assert!(
+            workspace.is_contiguous().is_some(),
+            "Moments: the workspace must be contiguous."
+        );
+
+        let shape_out = out.shape();
+        let shape_input = input.shape();
+        let params = MomentsParams {
+            variant: variant.id(),
+            correction: correction as f32,
+            padding: [0; 2],
+        };
+
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_input)?;
+        shapes.insert_params(backend, params)?;
+
+        let partial_args = MomentsArgs {
+            params: None,
+            shape_out: None,
+            shape_input: Some(shapes.get(shape_input).unwrap_or_else(|| unreachable!())),
+            out: None,
+            input: Some(input.buffer()),
+            partials: Some(workspace.buffer()),
+        };
+        self.moments_partial.launch_grid(
+            backend,
+            pass,
+            &partial_args,
+            [MOMENTS_NUM_PARTIALS, 1, 1],
+        )?;
+
+        let finalize_args = MomentsArgs {
+            params: Some(shapes.get_params(params).unwrap_or_else(|| unreachable!())),
+            shape_out: Some(shapes.get(shape_out).unwrap_or_else(|| unreachable!())),
+            shape_input: None,
+            out: Some(out.buffer()),
+            input: None,
+            partials: Some(workspace.buffer()),
+        };
+        self.moments_finalize
+            .launch_grid(backend, pass, &finalize_args, [1, 1, 1])
+    }
+
+    /// Launches the kernel computing the statistic `variant` of `input` along `axis`.
+    ///
+    /// The shape of `out` must be the same as `input`, except along `axis` where it must be 1.
+    /// See [`Self::launch`] for details on `correction`.
+    pub fn launch_axis<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: MomentsVariant,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        input: impl Into<GpuTensorView<'a, f32, B>>,
+        correction: u32,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let input = input.into();
+        assert!(axis < 4, "Moments: the axis must be smaller than 4.");
+
+        let mut expected_out_size = input.shape().size;
+        expected_out_size[axis] = 1;
+        assert_eq!(
+            out.shape().size,
+            expected_out_size,
+            "Moments: the output shape must match the input shape, with a size of 1 along the axis."
+        );
+
+        // The kernel always operates along the first dimension.
+        let shape_out = out.shape().swap_dims(0, axis);
+        let shape_input = input.shape().swap_dims(0, axis);
+        let params = MomentsParams {
+            variant: variant.id(),
+            correction: correction as f32,
+            padding: [0; 2],
+        };
+
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_input)?;
+        shapes.insert_params(backend, params)?;
+
+        let args = MomentsArgs {
+            params: Some(shapes.get_params(params).unwrap_or_else(|| unreachable!())),
+            shape_out: Some(shapes.get(shape_out).unwrap_or_else(|| unreachable!())),
+            shape_input: Some(shapes.get(shape_input).unwrap_or_else(|| unreachable!())),
+            out: Some(out.buffer()),
+            input: Some(input.buffer()),
+            partials: None,
+        };
+
+        // Each workgroup handles one line at a time. The cubes, and the lines past the capped
+        // grid, are iterated on by the kernel.
+        self.moments_axis.launch_grid(
+            backend,
+            pass,
+            &args,
+            [
+                shape_input.size[1].min(MAX_NUM_WORKGROUPS),
+                shape_input.size[2].min(MAX_NUM_WORKGROUPS),
+                1,
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MOMENTS_WORKSPACE_LEN, MomentsVariant};
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::{GpuTensor, TensorBuilder};
+    use approx::{RelativeEq, assert_relative_eq};
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    // NOTE: the reference is computed in f64 to check the precision of the gpu results.
+    fn cpu_moment(
+        variant: MomentsVariant,
+        values: impl Iterator<Item = f32>,
+        correction: u32,
+    ) -> f32 {
+        let values: Vec<f64> = values.map(|x| x as f64).collect();
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance =
+            values.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - correction as f64);
+        match variant {
+            MomentsVariant::Mean => mean as f32,
+            MomentsVariant::Variance => variance as f32,
+            MomentsVariant::Std => variance.sqrt() as f32,
+        }
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_moments_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_moments_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_moments_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_moments_generic(backend).await;
+    }
+
+    async fn gpu_moments_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let moments = super::Moments::from_backend(&backend, &compiler).unwrap();
+
+        // NOTE: the tensor is large enough to have several elements per thread for the
+        //       whole-tensor reduction.
+        const NROWS: usize = 1021;
+        const NCOLS: usize = 97;
+        const NMATS: usize = 3;
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let storage = BufferUsages::STORAGE;
+
+        let variants = [
+            MomentsVariant::Mean,
+            MomentsVariant::Variance,
+            MomentsVariant::Std,
+        ];
+
+        for variant in variants {
+            for correction in [0, 1] {
+                println!("Testing: {:?}, correction: {}", variant, correction);
+
+                // A large offset makes the naive sum-of-squares approach very inaccurate.
+                let inputs: Vec<_> = (0..NMATS)
+                    .map(|_| DMatrix::<f32>::new_random(NROWS, NCOLS).add_scalar(1000.0))
+                    .collect();
+                let flat_inputs: Vec<f32> = inputs.iter().flat_map(|m| m.iter().copied()).collect();
+                let shape = [NROWS as u32, NCOLS as u32, NMATS as u32, 1];
+
+                let gpu_input = TensorBuilder::tensor(shape, storage)
+                    .build_init(&backend, &flat_inputs)
+                    .unwrap();
+                let gpu_workspace =
+                    GpuTensor::vector(&backend, vec![0.0; MOMENTS_WORKSPACE_LEN as usize], storage)
+                        .unwrap();
+                let gpu_out = GpuTensor::scalar(&backend, 0.0, usage).unwrap();
+                let gpu_out_rows = TensorBuilder::tensor([1, NCOLS as u32, NMATS as u32, 1], usage)
+                    .build_init(&backend, &vec![0.0; NCOLS * NMATS])
+                    .unwrap();
+                let gpu_out_cols = TensorBuilder::tensor([NROWS as u32, 1, NMATS as u32, 1], usage)
+                    .build_init(&backend, &vec![0.0; NROWS * NMATS])
+                    .unwrap();
+
+                let mut shapes = ViewShapeBuffers::new(&backend);
+                let mut encoder = backend.begin_encoding();
+                let mut pass = encoder.begin_pass();
+                moments
+                    .launch(
+                        &backend,
+                        &mut shapes,
+                        &mut pass,
+                        variant,
+                        &gpu_out,
+                        &gpu_input,
+                        &gpu_workspace,
+                        correction,
+                    )
+                    .unwrap();
+                moments
+                    .launch_axis(
+                        &backend,
+                        &mut shapes,
+                        &mut pass,
+                        variant,
+                        0,
+                        &gpu_out_rows,
+                        &gpu_input,
+                        correction,
+                    )
+                    .unwrap();
+                moments
+                    .launch_axis(
+                        &backend,
+                        &mut shapes,
+                        &mut pass,
+                        variant,
+                        1,
+                        &gpu_out_cols,
+                        &gpu_input,
+                        correction,
+                    )
+                    .unwrap();
+                drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+                backend.submit(encoder).unwrap();
+
+                let mut gpu_result = [0.0];
+                let mut gpu_result_rows = vec![0.0; NCOLS * NMATS];
+                let mut gpu_result_cols = vec![0.0; NROWS * NMATS];
+                backend
+                    .slow_read_buffer(gpu_out.buffer(), &mut gpu_result)
+                    .await
+                    .unwrap();
+                backend
+                    .slow_read_buffer(gpu_out_rows.buffer(), &mut gpu_result_rows)
+                    .await
+                    .unwrap();
+                backend
+                    .slow_read_buffer(gpu_out_cols.buffer(), &mut gpu_result_cols)
+                    .await
+                    .unwrap();
+
+                let cpu_result = cpu_moment(variant, flat_inputs.iter().copied(), correction);
+                let cpu_result_rows: Vec<f32> = inputs
+                    .iter()
+                    .flat_map(|m| {
+                        m.column_iter()
+                            .map(|col| cpu_moment(variant, col.iter().copied(), correction))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                let cpu_result_cols: Vec<f32> = inputs
+                    .iter()
+                    .flat_map(|m| {
+                        m.row_iter()
+                            .map(|row| cpu_moment(variant, row.iter().copied(), correction))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+
+                assert_relative_eq!(gpu_result[0], cpu_result, max_relative = 1.0e-4);
+                assert_relative_eq!(
+                    DVector::from(gpu_result_rows),
+                    DVector::from(cpu_result_rows),
+                    max_relative = 1.0e-4
+                );
+                assert_relative_eq!(
+                    DVector::from(gpu_result_cols),
+                    DVector::from(cpu_result_cols),
+                    max_relative = 1.0e-4
+                );
+            }
+        }
+
+        // More lines than `MAX_NUM_WORKGROUPS` to check the capped grid.
+        const NLINES: usize = 70_001;
+        let input = DMatrix::<f32>::new_random(2, NLINES);
+        let gpu_input = GpuTensor::matrix(&backend, &input, BufferUsages::STORAGE).unwrap();
+        let gpu_out = TensorBuilder::matrix(1, NLINES as u32, usage)
+            .build_init(&backend, &vec![0.0; NLINES])
+            .unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        moments
+            .launch_axis(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                MomentsVariant::Mean,
+                0,
+                &gpu_out,
+                &gpu_input,
+                0,
+            )
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+        backend.submit(encoder).unwrap();
+
+        let mut gpu_result = vec![0.0; NLINES];
+        backend
+            .slow_read_buffer(gpu_out.buffer(), &mut gpu_result)
+            .await
+            .unwrap();
+        let cpu_result: Vec<f32> = input.column_iter().map(|col| col.mean()).collect();
+        // NOTE: don't use assert_relative_eq so it doesn't print out the whole vectors.
+        assert!(DVector::from(gpu_result).relative_eq(&DVector::from(cpu_result), 1.0e-5, 1.0e-5));
+    }
+}
@@ -0,0 +1,402 @@
+use crate::linalg::MAX_NUM_WORKGROUPS;
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// Slang module for layer normalization and root-mean-square normalization.
+///
+/// Each line of the tensor along the normalized axis is processed by a single workgroup, so the
+/// statistics and the normalization are computed in a single dispatch without temporary buffers.
+/// Workgroups loop over the lines when there are more than the maximum number of workgroups.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::norm")]
+pub struct Norm<B: Backend> {
+    /// Kernel for layer normalization along the first axis.
+    pub layer_norm: GpuFunction<B>,
+    /// Kernel for layer normalization along the first axis, followed by an element-wise affine
+    /// transformation.
+    pub layer_norm_affine: GpuFunction<B>,
+    /// Kernel for root-mean-square normalization along the first axis.
+    pub rms_norm: GpuFunction<B>,
+    /// Kernel for root-mean-square normalization along the first axis, followed by an element-wise
+    /// scaling.
+    pub rms_norm_affine: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct NormParams {
+    eps: f32,
+    padding: [u32; 3],
+}
+
+#[derive(ShaderArgs)]
+struct NormArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_out: &'a B::Buffer<ViewShape>,
+    shape_input: &'a B::Buffer<ViewShape>,
+    shape_weight: Option<&'a B::Buffer<ViewShape>>,
+    shape_bias: Option<&'a B::Buffer<ViewShape>>,
+    out: B::BufferSlice<'a, f32>,
+    input: B::BufferSlice<'a, f32>,
+    weight: Option<B::BufferSlice<'a, f32>>,
+    bias: Option<B::BufferSlice<'a, f32>>,
+}
+
+impl<B: Backend> Norm<B> {
+    /// Launches the kernel computing the layer normalization of `input` along `axis`.
+    ///
+    /// Each line `x` of `input` along `axis` is normalized as
+    /// `(x - mean(x)) / sqrt(variance(x) + eps)`, with the biased variance. If `affine` is
+    /// provided as `(weight, bias)`, the result is then multiplied by `weight` and `bias` is
+    /// added to it. Both must be vectors with as many elements as `input` along `axis`.
+    pub fn launch_layer_norm<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        input: impl Into<GpuTensorView<'a, f32, B>>,
+        affine: Option<(GpuTensorView<'a, f32, B>, GpuTensorView<'a, f32, B>)>,
+        eps: f32,
+    ) -> Result<(), B::Error> {
+        let (function, weight, bias) = match affine {
+            Some((weight, bias)) => (&self.layer_norm_affine, Some(weight), Some(bias)),
+            None => (&self.layer_norm, None, None),
+        };
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            function,
+            axis,
+            out.into(),
+            input.into(),
+            weight,
+            bias,
+            eps,
+        )
+    }
+
+    /// Launches the kernel computing the root-mean-square normalization of `input` along `axis`.
+    ///
+    /// Each line `x` of `input` along `axis` is normalized as `x / sqrt(mean(x²) + eps)`. If
+    /// `weight` is provided, the result is then multiplied by `weight`. It must be a vector with
+    /// as many elements as `input` along `axis`.
+    pub fn launch_rms_norm<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        input: impl Into<GpuTensorView<'a, f32, B>>,
+        weight: Option<GpuTensorView<'a, f32, B>>,
+        eps: f32,
+    ) -> Result<(), B::Error> {
+        let function = if weight.is_some() {
+            &self.rms_norm_affine
+        } else {
+            &self.rms_norm
+        };
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            function,
+            axis,
+            out.into(),
+            input.into(),
+            weight,
+            None,
+            eps,
+        )
+    }
+
+    fn launch_generic(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        function: &GpuFunction<B>,
+        axis: usize,
+        out: GpuTensorView<f32, B>,
+        input: GpuTensorView<f32, B>,
+        weight: Option<GpuTensorView<f32, B>>,
+        bias: Option<GpuTensorView<f32, B>>,
+        eps: f32,
+    ) -> Result<(), B::Error> {
+        assert!(axis < 4, "Norm: the axis must be smaller than 4.");
+        assert_eq!(
+            out.shape().size,
+            input.shape().size,
+            "Norm: the output and input shapes must be equal."
+        );
+
+        let len = input.shape().size[axis];
+        for affine in weight.iter().chain(bias.iter()) {
+            assert_eq!(
+                affine.shape().size,
+                [len, 1, 1, 1],
+                "Norm: the weight and bias must be vectors with {len} elements."
+            );
+        }
+
+        // The kernels always operate along the first dimension.
+        let shape_out = out.shape().swap_dims(0, axis);
+        let shape_input = input.shape().swap_dims(0, axis);
+        let shape_weight = weight.as_ref().map(|weight| weight.shape());
+        let shape_bias = bias.as_ref().map(|bias| bias.shape());
+        let params = NormParams {
+            eps,
+            padding: [0; 3],
+        };
+
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_input)?;
+        for shape in shape_weight.iter().chain(shape_bias.iter()) {
+            shapes.insert(backend, *shape)?;
+        }
+        shapes.insert_params(backend, params)?;
+
+        let shape_weight =
+            shape_weight.map(|shape| shapes.get(shape).unwrap_or_else(|| unreachable!()));
+        let shape_bias =
+            shape_bias.map(|shape| shapes.get(shape).unwrap_or_else(|| unreachable!()));
+        let args = NormArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+            shape_input: shapes.get(shape_input).unwrap_or_else(|| unreachable!()),
+            shape_weight,
+            shape_bias,
+            out: out.buffer(),
+            input: input.buffer(),
+            weight: weight.as_ref().map(|weight| weight.buffer()),
+            bias: bias.as_ref().map(|bias| bias.buffer()),
+        };
+
+        // Each workgroup handles one line at a time. The cubes, and the lines past the capped
+        // grid, are iterated on by the kernel.
+        function.launch_grid(
+            backend,
+            pass,
+            &args,
+            [
+                shape_out.size[1].min(MAX_NUM_WORKGROUPS),
+                shape_out.size[2].min(MAX_NUM_WORKGROUPS),
+                1,
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::{GpuTensor, TensorBuilder};
+    use approx::{RelativeEq, assert_relative_eq};
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    const EPS: f32 = 1.0e-5;
+
+    fn cpu_layer_norm(
+        x: &DVector<f32>,
+        weight: &DVector<f32>,
+        bias: &DVector<f32>,
+    ) -> DVector<f32> {
+        let mean = x.mean();
+        let variance = x.map(|x| (x - mean) * (x - mean)).mean();
+        let normalized = x.add_scalar(-mean) / (variance + EPS).sqrt();
+        normalized.component_mul(weight) + bias
+    }
+
+    fn cpu_rms_norm(x: &DVector<f32>) -> DVector<f32> {
+        let mean_sq = x.norm_squared() / x.len() as f32;
+        x / (mean_sq + EPS).sqrt()
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_norm_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_norm_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_norm_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_norm_generic(backend).await;
+    }
+
+    async fn gpu_norm_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let norm = super::Norm::from_backend(&backend, &compiler).unwrap();
+
+        // NOTE: the number of rows is larger than the workgroup size.
+        const NROWS: usize = 257;
+        const NCOLS: usize = 19;
+        const NMATS: usize = 3;
+        const NCUBES: usize = 2;
+        const NBATCH: usize = NMATS * NCUBES;
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let shape = [NROWS as u32, NCOLS as u32, NMATS as u32, NCUBES as u32];
+        let inputs: Vec<_> = (0..NBATCH)
+            .map(|_| DMatrix::<f32>::new_random(NROWS, NCOLS) * 10.0)
+            .collect();
+        let flat_inputs: Vec<f32> = inputs.iter().flat_map(|m| m.iter().copied()).collect();
+        let weight_rows = DVector::<f32>::new_random(NROWS);
+        let bias_rows = DVector::<f32>::new_random(NROWS);
+        let weight_cols = DVector::<f32>::new_random(NCOLS);
+        let bias_cols = DVector::<f32>::new_random(NCOLS);
+
+        let gpu_input = TensorBuilder::tensor(shape, usage)
+            .build_init(&backend, &flat_inputs)
+            .unwrap();
+        let gpu_weight_rows = GpuTensor::vector(&backend, &weight_rows, usage).unwrap();
+        let gpu_bias_rows = GpuTensor::vector(&backend, &bias_rows, usage).unwrap();
+        let gpu_weight_cols = GpuTensor::vector(&backend, &weight_cols, usage).unwrap();
+        let gpu_bias_cols = GpuTensor::vector(&backend, &bias_cols, usage).unwrap();
+        let outputs: [GpuTensor<f32, _>; 4] = std::array::from_fn(|_| {
+            TensorBuilder::tensor(shape, usage)
+                .build_init(&backend, &vec![0.0; flat_inputs.len()])
+                .unwrap()
+        });
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        // Normalize each column, i.e., along the axis 0.
+        norm.launch_layer_norm(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            0,
+            &outputs[0],
+            &gpu_input,
+            Some((gpu_weight_rows.as_view(), gpu_bias_rows.as_view())),
+            EPS,
+        )
+        .unwrap();
+        // Normalize each row, i.e., along the axis 1.
+        norm.launch_layer_norm(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            1,
+            &outputs[1],
+            &gpu_input,
+            Some((gpu_weight_cols.as_view(), gpu_bias_cols.as_view())),
+            EPS,
+        )
+        .unwrap();
+        norm.launch_rms_norm(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            1,
+            &outputs[2],
+            &gpu_input,
+            None,
+            EPS,
+        )
+        .unwrap();
+        norm.launch_rms_norm(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            1,
+            &outputs[3],
+            &gpu_input,
+            Some(gpu_weight_cols.as_view()),
+            EPS,
+        )
+        .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+
+        let mut gpu_results: [Vec<f32>; 4] = std::array::from_fn(|_| vec![0.0; flat_inputs.len()]);
+        for (output, result) in outputs.iter().zip(gpu_results.iter_mut()) {
+            backend
+                .slow_read_buffer(output.buffer(), result)
+                .await
+                .unwrap();
+        }
+
+        for (b, input) in inputs.iter().enumerate() {
+            let mut cpu_layer_norm_cols = input.clone();
+            for mut col in cpu_layer_norm_cols.column_iter_mut() {
+                let normalized = cpu_layer_norm(&col.clone_owned(), &weight_rows, &bias_rows);
+                col.copy_from(&normalized);
+            }
+
+            let mut cpu_layer_norm_rows = input.clone();
+            let mut cpu_rms_norm_rows = input.clone();
+            let mut cpu_rms_norm_affine_rows = input.clone();
+            for i in 0..NROWS {
+                let row = input.row(i).transpose();
+                let normalized = cpu_layer_norm(&row, &weight_cols, &bias_cols);
+                cpu_layer_norm_rows.row_mut(i).tr_copy_from(&normalized);
+                let rms_normalized = cpu_rms_norm(&row);
+                cpu_rms_norm_rows.row_mut(i).tr_copy_from(&rms_normalized);
+                cpu_rms_norm_affine_rows
+                    .row_mut(i)
+                    .tr_copy_from(&rms_normalized.component_mul(&weight_cols));
+            }
+
+            let range = b * NROWS * NCOLS..(b + 1) * NROWS * NCOLS;
+            let gpu_result =
+                |k: usize| DMatrix::from_column_slice(NROWS, NCOLS, &gpu_results[k][range.clone()]);
+            assert_relative_eq!(gpu_result(0), cpu_layer_norm_cols, epsilon = 1.0e-4);
+            assert_relative_eq!(gpu_result(1), cpu_layer_norm_rows, epsilon = 1.0e-4);
+            assert_relative_eq!(gpu_result(2), cpu_rms_norm_rows, epsilon = 1.0e-4);
+            assert_relative_eq!(gpu_result(3), cpu_rms_norm_affine_rows, epsilon = 1.0e-4);
+        }
+
+        // More lines than `MAX_NUM_WORKGROUPS` to check the capped grid.
+        const NLINES: usize = 70_001;
+        let input = DMatrix::<f32>::new_random(2, NLINES);
+        let gpu_input = GpuTensor::matrix(&backend, &input, usage).unwrap();
+        let gpu_out = GpuTensor::matrix(&backend, &DMatrix::zeros(2, NLINES), usage).unwrap();
+
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        norm.launch_rms_norm(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            0,
+            &gpu_out,
+            &gpu_input,
+            None,
+            EPS,
+        )
+        .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+        backend.submit(encoder).unwrap();
+
+        let mut gpu_result = DMatrix::zeros(2, NLINES);
+        backend
+            .slow_read_buffer(gpu_out.buffer(), gpu_result.as_mut_slice())
+            .await
+            .unwrap();
+        let mut cpu_result = input.clone();
+        for mut col in cpu_result.column_iter_mut() {
+            let normalized = cpu_rms_norm(&col.clone_owned());
+            col.copy_from(&normalized);
+        }
+        // NOTE: don't use assert_relative_eq so it doesn't print out the whole matrices.
+        assert!(gpu_result.relative_eq(&cpu_result, 1.0e-4, 1.0e-4));
+    }
+}
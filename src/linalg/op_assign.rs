@@ -16,6 +16,10 @@ pub enum OpAssignVariant {
     Mul,
     /// Division: `a /= b`
     Div,
+    /// Minimum: `a = min(a, b)`
+    Min,
+    /// Maximum: `a = max(a, b)`
+    Max,
     /// Copy: `a = b`
     Copy,
 }
@@ -32,6 +36,10 @@ pub struct OpAssign<B: Backend> {
     pub mul: GpuFunction<B>,
     /// Kernel for computing in-place the division of two tensors.
     pub div: GpuFunction<B>,
+    /// Kernel for computing in-place the element-wise minimum of two tensors.
+    pub minimum: GpuFunction<B>,
+    /// Kernel for computing in-place the element-wise maximum of two tensors.
+    pub maximum: GpuFunction<B>,
     /// Kernel for copying a tensor into another.
     pub copy: GpuFunction<B>,
     /// Kernel for copying a tensor into another, using a custom offset where to start reading
@@ -51,15 +59,120 @@ pub struct BinOpOffsets {
     pub padding: [u32; 2],
 }
 
+/// Arguments of the in-place binary operation kernels, including the ones from
+/// [`CustomBinOp`] implementations.
 #[derive(ShaderArgs)]
 pub struct BinOpArgs<'a, B: Backend> {
+    /// Shape of the tensor `a`.
     pub shape_a: &'a B::Buffer<ViewShape>,
+    /// Shape of the tensor `b`.
     pub shape_b: &'a B::Buffer<ViewShape>,
+    /// The first operand, read and written to.
     pub a: B::BufferSlice<'a, f32>,
+    /// The second operand, only read from.
     pub b: B::BufferSlice<'a, f32>,
+    /// Offsets only used by [`OpAssign::copy_with_offsets`].
     pub offsets: Option<B::BufferSlice<'a, BinOpOffsets>>,
 }
 
+/// A user-defined in-place binary operation, implemented in Slang through the `IBinOp`
+/// interface from `stensor.linalg.op_assign`.
+///
+/// The Slang module must implement `IBinOp` and declare an entry point calling the generic
+/// `main<Op: IBinOp>` kernel with the same parameters as the built-in operations:
+///
+/// ```slang
+/// import stensor.linalg.shape;
+/// import stensor.linalg.op_assign;
+///
+/// struct Hypot: IBinOp {
+///     static func binop(a: float, b: float) -> float {
+///         return sqrt(a * a + b * b);
+///     }
+/// }
+///
+/// [shader("compute")]
+/// [numthreads(WORKGROUP_SIZE, 1, 1)]
+/// func hypot(
+///     uint3 invocation_id: SV_DispatchThreadID,
+///     ConstantBuffer<Shape> shape_a,
+///     ConstantBuffer<Shape> shape_b,
+///     RWStructuredBuffer<float> a,
+///     StructuredBuffer<float> b,
+/// ) {
+///     main<Hypot>(invocation_id, shape_a, shape_b, a, b);
+/// }
+/// ```
+///
+/// On the Rust side, the entry point is loaded with `#[derive(Shader)]` using the module name,
+/// and exposed by implementing this trait:
+///
+/// ```ignore
+/// #[derive(Shader)]
+/// #[shader(module = "my_crate::hypot")]
+/// struct Hypot<B: Backend> {
+///     hypot: GpuFunction<B>,
+/// }
+///
+/// impl<B: Backend> CustomBinOp<B> for Hypot<B> {
+///     fn function(&self) -> &GpuFunction<B> {
+///         &self.hypot
+///     }
+/// }
+/// ```
+pub trait CustomBinOp<B: Backend> {
+    /// The compute kernel of this binary operation.
+    fn function(&self) -> &GpuFunction<B>;
+
+    /// Launches the kernel for this binary operation, where the first operand `a` is being read
+    /// & written to, and `b` is only being read from.
+    ///
+    /// This follows the same broadcasting rules as [`OpAssign::launch`].
+    fn launch<'b>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        a: impl Into<GpuTensorView<'b, f32, B>>,
+        b: impl Into<GpuTensorView<'b, f32, B>>,
+    ) -> Result<(), B::Error> {
+        launch_binop(self.function(), backend, shapes, pass, a.into(), b.into())
+    }
+}
+
+fn launch_binop<B: Backend>(
+    function: &GpuFunction<B>,
+    backend: &B,
+    shapes: &mut ViewShapeBuffers<B>,
+    pass: &mut B::Pass,
+    a: GpuTensorView<f32, B>,
+    b: GpuTensorView<f32, B>,
+) -> Result<(), B::Error> {
+    let shape_a = a.shape();
+    let shape_b = b.shape();
+    assert!(
+        shape_a.is_multiple_of(shape_b),
+        "shape_A: {:?} must be a multiple of shape_b: {:?}",
+        shape_a.size,
+        shape_b.size
+    );
+
+    shapes.insert(backend, shape_a)?;
+    shapes.insert(backend, shape_b)?;
+    let shape_a = shapes.get(shape_a).unwrap();
+    let shape_b = shapes.get(shape_b).unwrap();
+
+    let binop_args = BinOpArgs {
+        shape_a,
+        shape_b,
+        a: a.buffer(),
+        b: b.buffer(),
+        offsets: None,
+    };
+
+    function.launch_capped(backend, pass, &binop_args, a.len() as u32)
+}
+
 impl<B: Backend> OpAssign<B> {
     /// Launches the kernel for a binary operation `variant` where the first operand
     /// `a` being read & written to, and `b` is only being read from (e.g. `a += b`).
@@ -80,31 +193,11 @@ impl<B: Backend> OpAssign<B> {
             OpAssignVariant::Div => &self.div,
             OpAssignVariant::Mul => &self.mul,
             OpAssignVariant::Sub => &self.sub,
+            OpAssignVariant::Min => &self.minimum,
+            OpAssignVariant::Max => &self.maximum,
         };
 
-        let shape_a = a.shape();
-        let shape_b = b.shape();
-        assert!(
-            shape_a.is_multiple_of(shape_b),
-            "shape_A: {:?} must be a multiple of shape_b: {:?}",
-            shape_a.size,
-            shape_b.size
-        );
-
-        shapes.insert(backend, shape_a)?;
-        shapes.insert(backend, shape_b)?;
-        let shape_a = shapes.get(shape_a).unwrap();
-        let shape_b = shapes.get(shape_b).unwrap();
-
-        let binop_args = BinOpArgs {
-            shape_a,
-            shape_b,
-            a: a.buffer(),
-            b: b.buffer(),
-            offsets: None,
-        };
-
-        pipeline.launch_capped(backend, pass, &binop_args, a.len() as u32)
+        launch_binop(pipeline, backend, shapes, pass, a, b)
     }
 
     // FIXME: this only exists because we needed a quick fix to work arround the limitation on
@@ -154,15 +247,15 @@ impl<B: Backend> OpAssign<B> {
 
 #[cfg(test)]
 mod test {
-    use super::{BinOpArgs, OpAssignVariant};
+    use super::{BinOpArgs, CustomBinOp, OpAssignVariant};
     use crate::shapes::ViewShapeBuffers;
     use crate::tensor::GpuTensor;
     use minislang::SlangCompiler;
     use nalgebra::DVector;
-    use slang_hal::BufferUsages;
     use slang_hal::backend::WebGpu;
     use slang_hal::backend::{Backend, Buffer, Encoder};
-    use slang_hal::shader::Shader;
+    use slang_hal::function::GpuFunction;
+    use slang_hal::{BufferUsages, Shader};
 
     #[futures_test::test]
     #[serial_test::serial]
@@ -185,6 +278,8 @@ mod test {
             OpAssignVariant::Sub,
             OpAssignVariant::Mul,
             OpAssignVariant::Div,
+            OpAssignVariant::Min,
+            OpAssignVariant::Max,
             OpAssignVariant::Copy,
         ];
         let mut compiler = SlangCompiler::new(vec![]);
@@ -200,6 +295,8 @@ mod test {
                 OpAssignVariant::Sub => &op_assign.sub,
                 OpAssignVariant::Mul => &op_assign.mul,
                 OpAssignVariant::Div => &op_assign.div,
+                OpAssignVariant::Min => &op_assign.minimum,
+                OpAssignVariant::Max => &op_assign.maximum,
                 OpAssignVariant::Copy => &op_assign.copy,
             };
             let mut shapes = ViewShapeBuffers::new(&backend);
@@ -207,8 +304,15 @@ mod test {
 
             const LEN: u32 = 1757;
 
+            // The ordering of `v0` and `v1` alternates so `Min` and `Max` pick from both.
             let v0 = DVector::from_fn(LEN as usize, |i, _| i as f32 + 0.1);
-            let v1 = DVector::from_fn(LEN as usize, |i, _| i as f32 * 10.0 + 0.1);
+            let v1 = DVector::from_fn(LEN as usize, |i, _| {
+                if i % 2 == 0 {
+                    i as f32 * 10.0 + 0.2
+                } else {
+                    i as f32 * 0.1 + 0.1
+                }
+            });
             let mut gpu_result = DVector::zeros(LEN as usize);
             let gpu_v0 = GpuTensor::vector(
                 &backend,
@@ -248,6 +352,8 @@ mod test {
                 OpAssignVariant::Sub => v0 - v1,
                 OpAssignVariant::Mul => v0.component_mul(&v1),
                 OpAssignVariant::Div => v0.component_div(&v1),
+                OpAssignVariant::Min => v0.zip_map(&v1, |a, b| a.min(b)),
+                OpAssignVariant::Max => v0.zip_map(&v1, |a, b| a.max(b)),
                 OpAssignVariant::Copy => v1.clone(),
             };
 
@@ -255,4 +361,65 @@ mod test {
             approx::assert_relative_eq!(gpu_result, cpu_result, epsilon = 1.0e-7);
         }
     }
+
+    #[derive(Shader)]
+    #[shader(module = "stensor_tests::hypot")]
+    struct Hypot<B: Backend> {
+        hypot: GpuFunction<B>,
+    }
+
+    impl<B: Backend> CustomBinOp<B> for Hypot<B> {
+        fn function(&self) -> &GpuFunction<B> {
+            &self.hypot
+        }
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_custom_binop_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_custom_binop_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_custom_binop_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_custom_binop_generic(backend).await;
+    }
+
+    async fn gpu_custom_binop_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        compiler.add_dir(crate::TEST_SLANG_SRC_DIR.clone());
+        let hypot = Hypot::from_backend(&backend, &compiler).unwrap();
+
+        const LEN: usize = 1757;
+
+        let v0 = DVector::from_fn(LEN, |i, _| i as f32 + 0.1);
+        // A single element, broadcast to all the elements of `v0`.
+        let v1 = DVector::from_element(1, 3.0);
+        let mut gpu_result = DVector::zeros(LEN);
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let gpu_v0 = GpuTensor::vector(&backend, &v0, usage).unwrap();
+        let gpu_v1 = GpuTensor::vector(&backend, &v1, usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        hypot
+            .launch(&backend, &mut shapes, &mut pass, &gpu_v0, &gpu_v1)
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+        backend
+            .slow_read_buffer(gpu_v0.buffer(), gpu_result.as_mut_slice())
+            .await
+            .unwrap();
+
+        let cpu_result = v0.map(|x| x.hypot(3.0));
+        approx::assert_relative_eq!(gpu_result, cpu_result, epsilon = 1.0e-5);
+    }
 }
@@ -0,0 +1,304 @@
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorViewMut;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+use std::f32::consts::PI;
+
+const PHILOX_M0: u32 = 0xD2511F53;
+const PHILOX_M1: u32 = 0xCD9E8D57;
+const PHILOX_W0: u32 = 0x9E3779B9;
+const PHILOX_W1: u32 = 0xBB67AE85;
+
+/// The probability distribution sampled by the [`Random`] kernel.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum RandomDistribution {
+    /// Uniform distribution over `[low, high)`.
+    Uniform {
+        /// The (inclusive) lower bound of the samples.
+        low: f32,
+        /// The (exclusive) upper bound of the samples.
+        high: f32,
+    },
+    /// Normal distribution, sampled with the Box-Muller transform.
+    Normal {
+        /// The mean of the distribution.
+        mean: f32,
+        /// The standard deviation of the distribution.
+        std: f32,
+    },
+    /// Bernoulli distribution: each sample is `1.0` with probability `p` and `0.0` otherwise.
+    Bernoulli {
+        /// The probability of a sample being `1.0`.
+        p: f32,
+    },
+}
+
+impl RandomDistribution {
+    /// Computes on the CPU the `index`-th sample generated by the [`Random`] kernel for the given
+    /// `seed` and `offset`.
+    ///
+    /// This is a reference implementation giving the same results as the GPU (up to floating-point
+    /// rounding differences on transcendental functions for the normal distribution).
+    pub fn sample(self, seed: u64, offset: u64, index: u32) -> f32 {
+        let words = philox4x32_10(
+            [index / 4, 0, offset as u32, (offset >> 32) as u32],
+            [seed as u32, (seed >> 32) as u32],
+        );
+        let word = words[index as usize % 4];
+
+        match self {
+            Self::Uniform { low, high } => low + (high - low) * uint_to_unit_float(word),
+            Self::Normal { mean, std } => {
+                let pair = (index as usize % 4) / 2;
+                let u1 = uint_to_unit_float_nonzero(words[pair * 2]);
+                let u2 = uint_to_unit_float(words[pair * 2 + 1]);
+                let r = (-2.0 * u1.ln()).sqrt();
+                let theta = 2.0 * PI * u2;
+                let z = if index % 2 == 0 {
+                    r * theta.cos()
+                } else {
+                    r * theta.sin()
+                };
+                mean + std * z
+            }
+            Self::Bernoulli { p } => {
+                if uint_to_unit_float(word) < p {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// The Philox4x32-10 counter-based random number generator.
+///
+/// Generates four random 32-bits integers from the given `counter` and `key`. This is the
+/// generator used by the [`Random`] kernel.
+pub fn philox4x32_10(counter: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+    fn round(ctr: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+        let prod0 = PHILOX_M0 as u64 * ctr[0] as u64;
+        let prod1 = PHILOX_M1 as u64 * ctr[2] as u64;
+        let (hi0, lo0) = ((prod0 >> 32) as u32, prod0 as u32);
+        let (hi1, lo1) = ((prod1 >> 32) as u32, prod1 as u32);
+        [hi1 ^ ctr[1] ^ key[0], lo1, hi0 ^ ctr[3] ^ key[1], lo0]
+    }
+
+    let mut ctr = counter;
+    let mut key = key;
+
+    for _ in 0..9 {
+        ctr = round(ctr, key);
+        key[0] = key[0].wrapping_add(PHILOX_W0);
+        key[1] = key[1].wrapping_add(PHILOX_W1);
+    }
+
+    round(ctr, key)
+}
+
+fn uint_to_unit_float(x: u32) -> f32 {
+    (x >> 8) as f32 * (1.0 / 16777216.0)
+}
+
+fn uint_to_unit_float_nonzero(x: u32) -> f32 {
+    ((x >> 8) + 1) as f32 * (1.0 / 16777216.0)
+}
+
+/// Slang module for filling tensors with random samples.
+///
+/// The samples are generated by a counter-based random number generator (Philox4x32-10), so the
+/// result only depends on the seed, the offset, and the index of each element. Two launches with
+/// the same seed and offset generate the same samples on every backend. Use a different offset
+/// (or seed) for each launch to get independent samples.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::random")]
+pub struct Random<B: Backend> {
+    /// Kernel for sampling a uniform distribution.
+    pub uniform: GpuFunction<B>,
+    /// Kernel for sampling a normal distribution.
+    pub normal: GpuFunction<B>,
+    /// Kernel for sampling a Bernoulli distribution.
+    pub bernoulli: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct RandomParams {
+    seed: [u32; 2],
+    offset: [u32; 2],
+    a: f32,
+    b: f32,
+    padding: [u32; 2],
+}
+
+#[derive(ShaderArgs)]
+struct RandomArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape: &'a B::Buffer<ViewShape>,
+    out: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> Random<B> {
+    /// Launches the kernel filling `out` with samples of the given `distribution`.
+    ///
+    /// The `t`-th element of `out`, numbered in column-major order independently from its memory
+    /// layout, is set to `distribution.sample(seed, offset, t)`.
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        out: impl Into<GpuTensorViewMut<'a, f32, B>>,
+        distribution: RandomDistribution,
+        seed: u64,
+        offset: u64,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let (function, a, b) = match distribution {
+            RandomDistribution::Uniform { low, high } => (&self.uniform, low, high),
+            RandomDistribution::Normal { mean, std } => (&self.normal, mean, std),
+            RandomDistribution::Bernoulli { p } => (&self.bernoulli, p, 0.0),
+        };
+        let params = RandomParams {
+            seed: [seed as u32, (seed >> 32) as u32],
+            offset: [offset as u32, (offset >> 32) as u32],
+            a,
+            b,
+            padding: [0; 2],
+        };
+
+        let shape = out.shape();
+        shapes.insert(backend, shape)?;
+        shapes.insert_params(backend, params)?;
+
+        let args = RandomArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape: shapes.get(shape).unwrap_or_else(|| unreachable!()),
+            out: out.buffer(),
+        };
+        function.launch_capped(backend, pass, &args, shape.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RandomDistribution;
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[test]
+    fn philox_known_answer() {
+        // Known-answer tests from the Random123 library.
+        assert_eq!(
+            super::philox4x32_10([0; 4], [0; 2]),
+            [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]
+        );
+        assert_eq!(
+            super::philox4x32_10([u32::MAX; 4], [u32::MAX; 2]),
+            [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd]
+        );
+        assert_eq!(
+            super::philox4x32_10(
+                [0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344],
+                [0xa4093822, 0x299f31d0]
+            ),
+            [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1]
+        );
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_random_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_random_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_random_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_random_generic(backend).await;
+    }
+
+    async fn gpu_random_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let random = super::Random::from_backend(&backend, &compiler).unwrap();
+
+        const NROWS: usize = 131;
+        const NCOLS: usize = 17;
+        const SEED: u64 = 0x1234_5678_9abc_def0;
+        const OFFSET: u64 = (1 << 32) + 42;
+
+        let distributions = [
+            RandomDistribution::Uniform {
+                low: -2.0,
+                high: 3.0,
+            },
+            RandomDistribution::Normal {
+                mean: 1.0,
+                std: 0.5,
+            },
+            RandomDistribution::Bernoulli { p: 0.3 },
+        ];
+
+        for distribution in distributions {
+            println!("Testing: {:?}", distribution);
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut tensor = GpuTensor::matrix(
+                &backend,
+                &DMatrix::<f32>::zeros(NROWS, NCOLS),
+                BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            )
+            .unwrap();
+
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            random
+                .launch(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    &mut tensor,
+                    distribution,
+                    SEED,
+                    OFFSET,
+                )
+                .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+            backend.submit(encoder).unwrap();
+
+            let mut gpu_result = DMatrix::zeros(NROWS, NCOLS);
+            backend
+                .slow_read_buffer(tensor.buffer(), gpu_result.as_mut_slice())
+                .await
+                .unwrap();
+
+            let cpu_result = DMatrix::from_fn(NROWS, NCOLS, |i, j| {
+                distribution.sample(SEED, OFFSET, (i + j * NROWS) as u32)
+            });
+
+            approx::assert_relative_eq!(gpu_result, cpu_result, epsilon = 1.0e-4);
+
+            // Basic sanity check of the distribution’s first moment.
+            let mean = cpu_result.mean();
+            let expected_mean = match distribution {
+                RandomDistribution::Uniform { low, high } => (low + high) / 2.0,
+                RandomDistribution::Normal { mean, .. } => mean,
+                RandomDistribution::Bernoulli { p } => p,
+            };
+            approx::assert_relative_eq!(mean, expected_mean, epsilon = 0.1);
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use slang_hal::Shader;
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
 use slang_hal::backend::Backend;
 use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
 
 #[cfg(test)]
 use nalgebra::DVector;
@@ -50,24 +52,154 @@ pub struct Reduce<B: Backend> {
     pub reduce_sqnorm: GpuFunction<B>,
 }
 
+/// Arguments of the reduction kernels, including the ones from [`CustomReduction`]
+/// implementations.
+#[derive(ShaderArgs)]
+pub struct ReduceArgs<'a, B: Backend> {
+    /// Shape of the reduced vector.
+    pub shape: &'a B::Buffer<ViewShape>,
+    /// The vector to reduce.
+    pub input: B::BufferSlice<'a, f32>,
+    /// The one-element output.
+    pub output: B::BufferSlice<'a, f32>,
+}
+
+/// A user-defined reduction, implemented in Slang through the `IReduction` interface from
+/// `stensor.linalg.reduce`.
+///
+/// The Slang module must implement `IReduction` and declare an entry point calling the generic
+/// `main<R: IReduction>` kernel with the same parameters as the built-in reductions:
+///
+/// ```slang
+/// import stensor.linalg.shape;
+/// import stensor.linalg.reduce;
+///
+/// struct CountNonZeros: IReduction {
+///     static func init() -> float {
+///         return 0.0;
+///     }
+///     static func reduce_buffer(a: float, b: float) -> float {
+///         return b != 0.0 ? a + 1.0 : a;
+///     }
+///     static func reduce_workspace(a: float, b: float) -> float {
+///         return a + b;
+///     }
+/// }
+///
+/// [shader("compute")]
+/// [numthreads(WORKGROUP_SIZE, 1, 1)]
+/// void count_non_zeros(
+///     uint3 invocation_id: SV_DispatchThreadID,
+///     ConstantBuffer<Shape> shape,
+///     StructuredBuffer<float> input,
+///     RWStructuredBuffer<float> output,
+/// ) {
+///     main<CountNonZeros>(invocation_id, shape, input, output);
+/// }
+/// ```
+///
+/// On the Rust side, the entry point is loaded with `#[derive(Shader)]` using the module name,
+/// and exposed by implementing this trait:
+///
+/// ```ignore
+/// #[derive(Shader)]
+/// #[shader(module = "my_crate::count_non_zeros")]
+/// struct CountNonZeros<B: Backend> {
+///     count_non_zeros: GpuFunction<B>,
+/// }
+///
+/// impl<B: Backend> CustomReduction<B> for CountNonZeros<B> {
+///     fn function(&self) -> &GpuFunction<B> {
+///         &self.count_non_zeros
+///     }
+/// }
+/// ```
+pub trait CustomReduction<B: Backend> {
+    /// The compute kernel of this reduction.
+    fn function(&self) -> &GpuFunction<B>;
+
+    /// Launches the kernel reducing every element of the vector `input` into the one-element
+    /// tensor `output`.
+    fn launch<'b>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        output: impl Into<GpuTensorView<'b, f32, B>>,
+        input: impl Into<GpuTensorView<'b, f32, B>>,
+    ) -> Result<(), B::Error> {
+        launch_reduction(
+            self.function(),
+            backend,
+            shapes,
+            pass,
+            output.into(),
+            input.into(),
+        )
+    }
+}
+
+fn launch_reduction<B: Backend>(
+    function: &GpuFunction<B>,
+    backend: &B,
+    shapes: &mut ViewShapeBuffers<B>,
+    pass: &mut B::Pass,
+    output: GpuTensorView<f32, B>,
+    input: GpuTensorView<f32, B>,
+) -> Result<(), B::Error> {
+    let shape = input.shape();
+    assert_eq!(
+        shape.size[1..],
+        [1, 1, 1],
+        "Reduce: the input must be a vector."
+    );
+    assert_eq!(output.len(), 1, "Reduce: the output must have one element.");
+
+    shapes.insert(backend, shape)?;
+    let args = ReduceArgs {
+        shape: shapes.get(shape).unwrap_or_else(|| unreachable!()),
+        input: input.buffer(),
+        output: output.buffer(),
+    };
+
+    // A single workgroup handles the whole vector.
+    function.launch_grid(backend, pass, &args, [1, 1, 1])
+}
+
+impl<B: Backend> Reduce<B> {
+    /// Launches the kernel reducing every element of the vector `input` into the one-element
+    /// tensor `output`, with the operation given by `variant`.
+    pub fn launch<'b>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: ReduceVariant,
+        output: impl Into<GpuTensorView<'b, f32, B>>,
+        input: impl Into<GpuTensorView<'b, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let function = match variant {
+            ReduceVariant::Min => &self.reduce_min,
+            ReduceVariant::Max => &self.reduce_max,
+            ReduceVariant::Sum => &self.reduce_sum,
+            ReduceVariant::Prod => &self.reduce_product,
+            ReduceVariant::SqNorm => &self.reduce_sqnorm,
+        };
+        launch_reduction(function, backend, shapes, pass, output.into(), input.into())
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::ReduceVariant;
-    use crate::shapes::{ViewShape, ViewShapeBuffers};
+    use super::{CustomReduction, ReduceVariant};
+    use crate::shapes::ViewShapeBuffers;
     use crate::tensor::GpuTensor;
     use minislang::SlangCompiler;
     use nalgebra::DVector;
     use slang_hal::backend::WebGpu;
     use slang_hal::backend::{Backend, Encoder};
-    use slang_hal::shader::Shader;
-    use slang_hal::{BufferUsages, ShaderArgs};
-
-    #[derive(ShaderArgs)]
-    pub struct ReduceArgs<'a, B: Backend> {
-        pub shape: &'a B::Buffer<ViewShape>,
-        pub input: &'a B::Buffer<f32>,
-        pub output: &'a B::Buffer<f32>,
-    }
+    use slang_hal::function::GpuFunction;
+    use slang_hal::{BufferUsages, Shader};
 
     #[futures_test::test]
     #[serial_test::serial]
@@ -100,13 +232,6 @@ mod test {
         for op in ops {
             println!("Testing: {:?}", op);
 
-            let function = match op {
-                ReduceVariant::Min => &reduce.reduce_min,
-                ReduceVariant::Max => &reduce.reduce_max,
-                ReduceVariant::Sum => &reduce.reduce_sum,
-                ReduceVariant::Prod => &reduce.reduce_product,
-                ReduceVariant::SqNorm => &reduce.reduce_sqnorm,
-            };
             let mut shapes = ViewShapeBuffers::new(&backend);
             let mut encoder = backend.begin_encoding();
 
@@ -122,18 +247,9 @@ mod test {
             )
             .unwrap();
 
-            shapes.insert(&backend, gpu_v.as_view().shape()).unwrap();
-
-            let shape = shapes.get(gpu_v.as_view().shape()).unwrap();
-
             let mut pass = encoder.begin_pass();
-            let binop_args = ReduceArgs {
-                shape,
-                input: gpu_v.buffer(),
-                output: gpu_out.buffer(),
-            };
-            function
-                .launch(&backend, &mut pass, &binop_args, [1, 1, 1])
+            reduce
+                .launch(&backend, &mut shapes, &mut pass, op, &gpu_out, &gpu_v)
                 .unwrap();
             drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
 
@@ -148,4 +264,81 @@ mod test {
             approx::assert_relative_eq!(gpu_result[0], cpu_result, epsilon = 1.0e-3);
         }
     }
+
+    #[derive(Shader)]
+    #[shader(module = "stensor_tests::l1_norm")]
+    struct L1Norm<B: Backend> {
+        l1_norm: GpuFunction<B>,
+    }
+
+    impl<B: Backend> CustomReduction<B> for L1Norm<B> {
+        fn function(&self) -> &GpuFunction<B> {
+            &self.l1_norm
+        }
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_custom_reduction_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_custom_reduction_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_custom_reduction_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_custom_reduction_generic(backend).await;
+    }
+
+    async fn gpu_custom_reduction_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        compiler.add_dir(crate::TEST_SLANG_SRC_DIR.clone());
+        let l1_norm = L1Norm::from_backend(&backend, &compiler).unwrap();
+        let reduce = super::Reduce::from_backend(&backend, &compiler).unwrap();
+
+        const LEN: usize = 345;
+
+        let v = DVector::<f32>::new_random(LEN).add_scalar(-0.5);
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let gpu_v = GpuTensor::vector(&backend, &v, usage).unwrap();
+        let gpu_l1_norm = GpuTensor::scalar(&backend, 0.0, usage).unwrap();
+        let gpu_max = GpuTensor::scalar(&backend, 0.0, usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        l1_norm
+            .launch(&backend, &mut shapes, &mut pass, &gpu_l1_norm, &gpu_v)
+            .unwrap();
+        reduce
+            .launch(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                ReduceVariant::Max,
+                &gpu_max,
+                &gpu_v,
+            )
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+
+        let mut result_l1_norm = [0.0];
+        let mut result_max = [0.0];
+        backend
+            .slow_read_buffer(gpu_l1_norm.buffer(), &mut result_l1_norm)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_max.buffer(), &mut result_max)
+            .await
+            .unwrap();
+
+        approx::assert_relative_eq!(result_l1_norm[0], v.lp_norm(1), epsilon = 1.0e-3);
+        approx::assert_relative_eq!(result_max[0], v.max());
+    }
 }
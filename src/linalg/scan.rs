@@ -0,0 +1,467 @@
+use crate::linalg::MAX_NUM_WORKGROUPS;
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use bytemuck::NoUninit;
+use slang_hal::backend::{Backend, DeviceValue};
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+const WORKGROUP_SIZE: u32 = 256;
+// Alignment (in number of elements) of each level of the workspace. This matches the most
+// restrictive storage buffer offset alignment (256 bytes) for 4-bytes elements.
+const WORKSPACE_ALIGN: u32 = 64;
+
+/// The desired operation for the [`Scan`] kernel.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum ScanVariant {
+    /// Cumulative sum: `out[i] = input[0] + ... + input[i]`
+    Sum,
+    /// Cumulative product: `out[i] = input[0] * ... * input[i]`
+    Prod,
+    /// Cumulative maximum: `out[i] = max(input[0], ..., input[i])`
+    Max,
+    /// Cumulative minimum: `out[i] = min(input[0], ..., input[i])`
+    Min,
+}
+
+impl ScanVariant {
+    fn id(self) -> u32 {
+        match self {
+            Self::Sum => 0,
+            Self::Prod => 1,
+            Self::Max => 2,
+            Self::Min => 3,
+        }
+    }
+}
+
+/// Scalar types supported by the [`Scan`] kernel.
+///
+/// This is implemented for `f32` and `u32`.
+pub trait ScanValue: DeviceValue + NoUninit {
+    #[doc(hidden)]
+    fn scan_functions<B: Backend>(scan: &Scan<B>) -> (&GpuFunction<B>, &GpuFunction<B>);
+}
+
+impl ScanValue for f32 {
+    fn scan_functions<B: Backend>(scan: &Scan<B>) -> (&GpuFunction<B>, &GpuFunction<B>) {
+        (&scan.scan_blocks_f32, &scan.propagate_f32)
+    }
+}
+
+impl ScanValue for u32 {
+    fn scan_functions<B: Backend>(scan: &Scan<B>) -> (&GpuFunction<B>, &GpuFunction<B>) {
+        (&scan.scan_blocks_u32, &scan.propagate_u32)
+    }
+}
+
+/// Slang module for computing prefix scans (cumulative sums, products, maximums, and minimums)
+/// along an axis.
+///
+/// Lines of any length are supported by scanning blocks independently, scanning the blocks’
+/// totals recursively, and then propagating the totals back to each block.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::scan")]
+pub struct Scan<B: Backend> {
+    /// Kernel scanning each block of `f32` values along the first axis.
+    pub scan_blocks_f32: GpuFunction<B>,
+    /// Kernel scanning each block of `u32` values along the first axis.
+    pub scan_blocks_u32: GpuFunction<B>,
+    /// Kernel combining each block of `f32` values with the total of the previous blocks.
+    pub propagate_f32: GpuFunction<B>,
+    /// Kernel combining each block of `u32` values with the total of the previous blocks.
+    pub propagate_u32: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ScanParams {
+    op: u32,
+    exclusive: u32,
+    padding: [u32; 2],
+}
+
+#[derive(ShaderArgs)]
+struct ScanArgs<'a, T: DeviceValue, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_out: &'a B::Buffer<ViewShape>,
+    shape_input: Option<&'a B::Buffer<ViewShape>>,
+    shape_totals: &'a B::Buffer<ViewShape>,
+    out: B::BufferSlice<'a, T>,
+    input: Option<B::BufferSlice<'a, T>>,
+    totals: B::BufferSlice<'a, T>,
+}
+
+/// The offset and number of blocks of each level of the scan workspace.
+fn workspace_levels(size: [u32; 4]) -> (Vec<(u32, u32)>, u32) {
+    let num_lines = size[1] * size[2] * size[3];
+    let mut levels = vec![];
+    let mut offset = 0;
+    let mut len = size[0];
+
+    loop {
+        let num_blocks = len.div_ceil(WORKGROUP_SIZE);
+        levels.push((offset, num_blocks));
+        offset += (num_blocks * num_lines).next_multiple_of(WORKSPACE_ALIGN);
+
+        if num_blocks <= 1 {
+            return (levels, offset);
+        }
+
+        len = num_blocks;
+    }
+}
+
+impl<B: Backend> Scan<B> {
+    /// The minimum number of elements of the workspace needed for scanning a tensor with the
+    /// given `shape` along `axis`.
+    pub fn workspace_len(&self, shape: ViewShape, axis: usize) -> u32 {
+        workspace_levels(shape.swap_dims(0, axis).size).1
+    }
+
+    /// Launches the kernels computing the prefix scan `variant` of `input` along `axis`.
+    ///
+    /// If `exclusive` is `true`, the `i`-th output element doesn’t include the `i`-th input
+    /// element (and the first output element is set to the operation’s identity). The `workspace`
+    /// must be contiguous, must have at least [`Self::workspace_len`] elements, and is used for
+    /// storing the intermediate totals of each block.
+    pub fn launch<'a, T: ScanValue>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: ScanVariant,
+        exclusive: bool,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, T, B>>,
+        input: impl Into<GpuTensorView<'a, T, B>>,
+        workspace: impl Into<GpuTensorView<'a, T, B>>,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let input = input.into();
+        let workspace = workspace.into();
+        assert!(axis < 4, "Scan: the axis must be smaller than 4.");
+        assert_eq!(
+            out.shape().size,
+            input.shape().size,
+            "Scan: the output and input shapes must be equal."
+        );
+
+        // The kernels always operate along the first dimension.
+        let shape_out = out.shape().swap_dims(0, axis);
+        let shape_input = input.shape().swap_dims(0, axis);
+        let [_, ncols, nmats, ncubes] = shape_out.size;
+
+        if out.len() == 0 {
+            return Ok(());
+        }
+
+        let (levels, workspace_len) = workspace_levels(shape_out.size);
+        assert!(
+            workspace.len() >= workspace_len as u64,
+            "Scan: the workspace must have at least {workspace_len} elements."
+        );
+        assert!(
+            workspace.is_contiguous().is_some(),
+            "Scan: the workspace must be contiguous."
+        );
+
+        let (scan_blocks, propagate) = T::scan_functions(self);
+        let params = ScanParams {
+            op: variant.id(),
+            exclusive: exclusive as u32,
+            padding: [0; 2],
+        };
+        // The intermediate levels are always scanned inclusively.
+        let totals_params = ScanParams {
+            exclusive: 0,
+            ..params
+        };
+        let totals: Vec<_> = levels
+            .iter()
+            .map(|(offset, num_blocks)| {
+                workspace.view(*offset, [*num_blocks, ncols, nmats, ncubes], [None; 4])
+            })
+            .collect();
+
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_input)?;
+        for level in &totals {
+            shapes.insert(backend, level.shape())?;
+        }
+        shapes.insert_params(backend, params)?;
+        shapes.insert_params(backend, totals_params)?;
+
+        let shapes = &*shapes;
+        let get_shape = |shape| shapes.get(shape).unwrap_or_else(|| unreachable!());
+        let get_params = |params| shapes.get_params(params).unwrap_or_else(|| unreachable!());
+        let grid = |num_blocks: u32| {
+            [
+                num_blocks.min(MAX_NUM_WORKGROUPS),
+                shape_out.size[1].min(MAX_NUM_WORKGROUPS),
+                shape_out.size[2].min(MAX_NUM_WORKGROUPS),
+            ]
+        };
+
+        // 1. Scan each block of the input, and its totals, recursively.
+        let args = ScanArgs {
+            params: get_params(params),
+            shape_out: get_shape(shape_out),
+            shape_input: Some(get_shape(shape_input)),
+            shape_totals: get_shape(totals[0].shape()),
+            out: out.buffer(),
+            input: Some(input.buffer()),
+            totals: totals[0].buffer(),
+        };
+        scan_blocks.launch_grid(backend, pass, &args, grid(levels[0].1))?;
+
+        for i in 0..totals.len() - 1 {
+            let args = ScanArgs {
+                params: get_params(totals_params),
+                shape_out: get_shape(totals[i].shape()),
+                shape_input: Some(get_shape(totals[i].shape())),
+                shape_totals: get_shape(totals[i + 1].shape()),
+                out: totals[i].buffer(),
+                input: Some(totals[i].buffer()),
+                totals: totals[i + 1].buffer(),
+            };
+            scan_blocks.launch_grid(backend, pass, &args, grid(levels[i + 1].1))?;
+        }
+
+        // 2. Propagate the scanned totals, from the coarsest level to the output.
+        for i in (0..totals.len() - 1).rev() {
+            let args = ScanArgs {
+                params: get_params(totals_params),
+                shape_out: get_shape(totals[i].shape()),
+                shape_input: None,
+                shape_totals: get_shape(totals[i + 1].shape()),
+                out: totals[i].buffer(),
+                input: None,
+                totals: totals[i + 1].buffer(),
+            };
+            propagate.launch_capped(backend, pass, &args, totals[i].len() as u32)?;
+        }
+
+        if totals.len() > 1 {
+            let args = ScanArgs {
+                params: get_params(params),
+                shape_out: get_shape(shape_out),
+                shape_input: None,
+                shape_totals: get_shape(totals[0].shape()),
+                out: out.buffer(),
+                input: None,
+                totals: totals[0].buffer(),
+            };
+            propagate.launch_capped(backend, pass, &args, out.len() as u32)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ScanVariant;
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::{GpuTensor, TensorBuilder};
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    fn cpu_scan<T: Copy>(
+        values: impl Iterator<Item = T>,
+        identity: T,
+        exclusive: bool,
+        op: impl Fn(T, T) -> T,
+    ) -> Vec<T> {
+        let mut acc = identity;
+        values
+            .map(|x| {
+                let prev = acc;
+                acc = op(acc, x);
+                if exclusive { prev } else { acc }
+            })
+            .collect()
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_scan_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_scan_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_scan_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_scan_generic(backend).await;
+    }
+
+    async fn gpu_scan_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let scan = super::Scan::from_backend(&backend, &compiler).unwrap();
+
+        // NOTE: this needs three levels of blocks.
+        const LEN: usize = 300_001;
+        const NROWS: usize = 37;
+        const NCOLS: usize = 613;
+        // More lines than `MAX_NUM_WORKGROUPS` to check the capped grid.
+        const NLINES: usize = 70_001;
+        const LINE_LEN: usize = 3;
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+
+        /*
+         * Exclusive u32 prefix sum of a long vector.
+         */
+        let values: Vec<u32> = (0..LEN).map(|i| (i as u32 * 7919) % 13).collect();
+        let gpu_values = GpuTensor::vector(&backend, &values, usage).unwrap();
+        let gpu_sums = GpuTensor::vector(&backend, vec![0u32; LEN], usage).unwrap();
+        let workspace_len = scan.workspace_len(gpu_values.as_view().shape(), 0);
+        let gpu_workspace_u32 =
+            GpuTensor::vector(&backend, vec![0u32; workspace_len as usize], usage).unwrap();
+
+        /*
+         * f32 inclusive scans along the rows of a matrix.
+         */
+        let matrix = DMatrix::<f32>::new_random(NROWS, NCOLS).add_scalar(0.5);
+        let gpu_matrix = GpuTensor::matrix(&backend, &matrix, usage).unwrap();
+        let variants = [
+            ScanVariant::Sum,
+            ScanVariant::Prod,
+            ScanVariant::Max,
+            ScanVariant::Min,
+        ];
+        let gpu_results: Vec<_> = variants
+            .iter()
+            .map(|_| {
+                TensorBuilder::matrix(NROWS as u32, NCOLS as u32, usage)
+                    .build_init(&backend, &vec![0.0f32; NROWS * NCOLS])
+                    .unwrap()
+            })
+            .collect();
+        let workspace_len = scan.workspace_len(gpu_matrix.as_view().shape(), 1);
+        let gpu_workspaces_f32: Vec<_> = variants
+            .iter()
+            .map(|_| GpuTensor::vector(&backend, vec![0.0f32; workspace_len as usize], usage))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        /*
+         * u32 inclusive scans of many short columns.
+         */
+        let lines: Vec<u32> = (0..NLINES * LINE_LEN).map(|i| i as u32 % 5).collect();
+        let gpu_lines = TensorBuilder::matrix(LINE_LEN as u32, NLINES as u32, usage)
+            .build_init(&backend, &lines)
+            .unwrap();
+        let gpu_lines_sums = TensorBuilder::matrix(LINE_LEN as u32, NLINES as u32, usage)
+            .build_init(&backend, &vec![0u32; lines.len()])
+            .unwrap();
+        let workspace_len = scan.workspace_len(gpu_lines.as_view().shape(), 0);
+        let gpu_lines_workspace =
+            GpuTensor::vector(&backend, vec![0u32; workspace_len as usize], usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        scan.launch(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            ScanVariant::Sum,
+            true,
+            0,
+            &gpu_sums,
+            &gpu_values,
+            &gpu_workspace_u32,
+        )
+        .unwrap();
+        scan.launch(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            ScanVariant::Sum,
+            false,
+            0,
+            &gpu_lines_sums,
+            &gpu_lines,
+            &gpu_lines_workspace,
+        )
+        .unwrap();
+        for ((variant, result), workspace) in variants
+            .iter()
+            .zip(gpu_results.iter())
+            .zip(gpu_workspaces_f32.iter())
+        {
+            scan.launch(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                *variant,
+                false,
+                1,
+                result,
+                &gpu_matrix,
+                workspace,
+            )
+            .unwrap();
+        }
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+
+        let mut sums = vec![0u32; LEN];
+        backend
+            .slow_read_buffer(gpu_sums.buffer(), &mut sums)
+            .await
+            .unwrap();
+        let cpu_sums = cpu_scan(values.iter().copied(), 0, true, |a, b| a + b);
+        assert_eq!(sums, cpu_sums);
+
+        let mut lines_sums = vec![0u32; lines.len()];
+        backend
+            .slow_read_buffer(gpu_lines_sums.buffer(), &mut lines_sums)
+            .await
+            .unwrap();
+        let cpu_lines_sums: Vec<_> = lines
+            .chunks(LINE_LEN)
+            .flat_map(|line| cpu_scan(line.iter().copied(), 0, false, |a, b| a + b))
+            .collect();
+        assert!(lines_sums == cpu_lines_sums);
+
+        for (variant, gpu_result) in variants.iter().zip(gpu_results.iter()) {
+            println!("Testing: {:?}", variant);
+
+            let mut result = DMatrix::zeros(NROWS, NCOLS);
+            backend
+                .slow_read_buffer(gpu_result.buffer(), result.as_mut_slice())
+                .await
+                .unwrap();
+
+            let mut cpu_result = DMatrix::zeros(NROWS, NCOLS);
+            for i in 0..NROWS {
+                let row = matrix.row(i);
+                let scanned = match variant {
+                    ScanVariant::Sum => cpu_scan(row.iter().copied(), 0.0, false, |a, b| a + b),
+                    ScanVariant::Prod => cpu_scan(row.iter().copied(), 1.0, false, |a, b| a * b),
+                    ScanVariant::Max => {
+                        cpu_scan(row.iter().copied(), f32::MIN, false, |a, b| a.max(b))
+                    }
+                    ScanVariant::Min => {
+                        cpu_scan(row.iter().copied(), f32::MAX, false, |a, b| a.min(b))
+                    }
+                };
+                cpu_result.row_mut(i).tr_copy_from(&DVector::from(scanned));
+            }
+
+            assert_relative_eq!(result, cpu_result, max_relative = 1.0e-4);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use crate::linalg::MAX_NUM_WORKGROUPS;
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// The desired operation for the [`Softmax`] kernel.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum SoftmaxVariant {
+    /// Softmax: `out[i] = exp(x[i]) / Σ exp(x[j])`
+    Softmax,
+    /// Log-softmax: `out[i] = x[i] - log(Σ exp(x[j]))`
+    LogSoftmax,
+}
+
+/// Slang module for computing the (log-)softmax of a tensor along an axis.
+///
+/// The computation is numerically stable: the maximum of each line is subtracted before
+/// exponentiation.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::softmax")]
+pub struct Softmax<B: Backend> {
+    /// Kernel for computing the softmax along the first axis.
+    pub softmax: GpuFunction<B>,
+    /// Kernel for computing the log-softmax along the first axis.
+    pub log_softmax: GpuFunction<B>,
+    /// Kernel for computing the softmax along the first axis, after adding a mask to the input.
+    pub softmax_masked: GpuFunction<B>,
+    /// Kernel for computing the log-softmax along the first axis, after adding a mask to the
+    /// input.
+    pub log_softmax_masked: GpuFunction<B>,
+}
+
+#[derive(ShaderArgs)]
+struct SoftmaxArgs<'a, B: Backend> {
+    shape_out: &'a B::Buffer<ViewShape>,
+    shape_input: &'a B::Buffer<ViewShape>,
+    shape_mask: Option<&'a B::Buffer<ViewShape>>,
+    out: B::BufferSlice<'a, f32>,
+    input: B::BufferSlice<'a, f32>,
+    mask: Option<B::BufferSlice<'a, f32>>,
+}
+
+impl<B: Backend> Softmax<B> {
+    /// Launches the kernel computing `out = softmax(input + mask)` (or its logarithm, depending on
+    /// `variant`) along the given `axis`.
+    ///
+    /// The `mask` is added to `input` before the softmax is computed. Its shape must divide the
+    /// shape of `input`; it is repeated to match it. A mask value of `f32::NEG_INFINITY` results
+    /// in a probability of zero, unless every element of the line is masked this way.
+    pub fn launch<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: SoftmaxVariant,
+        axis: usize,
+        out: impl Into<GpuTensorView<'a, f32, B>>,
+        input: impl Into<GpuTensorView<'a, f32, B>>,
+        mask: Option<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let out = out.into();
+        let input = input.into();
+        assert_eq!(
+            out.shape().size,
+            input.shape().size,
+            "Softmax: the output and input shapes must be equal."
+        );
+        self.launch_generic(backend, shapes, pass, variant, axis, out, input, mask)
+    }
+
+    /// Launches the kernel computing in-place `inout = softmax(inout + mask)` (or its logarithm,
+    /// depending on `variant`) along the given `axis`.
+    ///
+    /// See [`Self::launch`] for details on the `mask`.
+    pub fn launch_inplace<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: SoftmaxVariant,
+        axis: usize,
+        inout: impl Into<GpuTensorView<'a, f32, B>>,
+        mask: Option<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let inout = inout.into();
+        self.launch_generic(backend, shapes, pass, variant, axis, inout, inout, mask)
+    }
+
+    fn launch_generic(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        variant: SoftmaxVariant,
+        axis: usize,
+        out: GpuTensorView<f32, B>,
+        input: GpuTensorView<f32, B>,
+        mask: Option<GpuTensorView<f32, B>>,
+    ) -> Result<(), B::Error> {
+        assert!(axis < 4, "Softmax: the axis must be smaller than 4.");
+
+        let function = match (variant, mask.is_some()) {
+            (SoftmaxVariant::Softmax, false) => &self.softmax,
+            (SoftmaxVariant::LogSoftmax, false) => &self.log_softmax,
+            (SoftmaxVariant::Softmax, true) => &self.softmax_masked,
+            (SoftmaxVariant::LogSoftmax, true) => &self.log_softmax_masked,
+        };
+
+        // The kernels always operate along the first dimension.
+        let shape_out = out.shape().swap_dims(0, axis);
+        let shape_input = input.shape().swap_dims(0, axis);
+        let shape_mask = mask.as_ref().map(|mask| {
+            assert!(
+                shape_input.is_multiple_of(mask.shape().swap_dims(0, axis)),
+                "Softmax: the mask shape {:?} must divide the input shape {:?}.",
+                mask.shape().size,
+                input.shape().size
+            );
+            mask.shape().swap_dims(0, axis)
+        });
+
+        shapes.insert(backend, shape_out)?;
+        shapes.insert(backend, shape_input)?;
+        if let Some(shape_mask) = shape_mask {
+            shapes.insert(backend, shape_mask)?;
+        }
+
+        let shape_mask =
+            shape_mask.map(|shape| shapes.get(shape).unwrap_or_else(|| unreachable!()));
+        let args = SoftmaxArgs {
+            shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+            shape_input: shapes.get(shape_input).unwrap_or_else(|| unreachable!()),
+            shape_mask,
+            out: out.buffer(),
+            input: input.buffer(),
+            mask: mask.as_ref().map(|mask| mask.buffer()),
+        };
+
+        // Each workgroup handles one line at a time. The cubes, and the lines past the capped
+        // grid, are iterated on by the kernel.
+        function.launch_grid(
+            backend,
+            pass,
+            &args,
+            [
+                shape_out.size[1].min(MAX_NUM_WORKGROUPS),
+                shape_out.size[2].min(MAX_NUM_WORKGROUPS),
+                1,
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SoftmaxVariant;
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use approx::{RelativeEq, assert_relative_eq};
+    use minislang::SlangCompiler;
+    use nalgebra::{DMatrix, DVector};
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    fn cpu_softmax(variant: SoftmaxVariant, x: &DVector<f32>) -> DVector<f32> {
+        let max = x.max();
+        let sum: f32 = x.iter().map(|x| (x - max).exp()).sum();
+        match variant {
+            SoftmaxVariant::Softmax => x.map(|x| (x - max).exp() / sum),
+            SoftmaxVariant::LogSoftmax => x.map(|x| x - max - sum.ln()),
+        }
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_softmax_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_softmax_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_softmax_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_softmax_generic(backend).await;
+    }
+
+    async fn gpu_softmax_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let softmax = super::Softmax::from_backend(&backend, &compiler).unwrap();
+
+        // NOTE: the number of rows is larger than the workgroup size.
+        const NROWS: usize = 301;
+        const NCOLS: usize = 37;
+
+        for variant in [SoftmaxVariant::Softmax, SoftmaxVariant::LogSoftmax] {
+            println!("Testing: {:?}", variant);
+
+            let input = DMatrix::<f32>::new_random(NROWS, NCOLS) * 20.0;
+            // Mask every third column, broadcast on all the rows.
+            let mask = DMatrix::from_fn(
+                1,
+                NCOLS,
+                |_, j| {
+                    if j % 3 == 0 { f32::NEG_INFINITY } else { 0.0 }
+                },
+            );
+
+            let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+            let gpu_input = GpuTensor::matrix(&backend, &input, usage).unwrap();
+            let gpu_mask = GpuTensor::matrix(&backend, &mask, usage).unwrap();
+            let gpu_out =
+                GpuTensor::matrix(&backend, &DMatrix::zeros(NROWS, NCOLS), usage).unwrap();
+            let gpu_inplace = GpuTensor::matrix(&backend, &input, usage).unwrap();
+
+            let mut shapes = ViewShapeBuffers::new(&backend);
+            let mut encoder = backend.begin_encoding();
+            let mut pass = encoder.begin_pass();
+            // Out-of-place along the columns.
+            softmax
+                .launch(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    variant,
+                    0,
+                    &gpu_out,
+                    &gpu_input,
+                    None,
+                )
+                .unwrap();
+            // In-place along the rows, with a mask.
+            softmax
+                .launch_inplace(
+                    &backend,
+                    &mut shapes,
+                    &mut pass,
+                    variant,
+                    1,
+                    &gpu_inplace,
+                    Some(gpu_mask.as_view()),
+                )
+                .unwrap();
+            drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+            backend.submit(encoder).unwrap();
+
+            let mut gpu_result = DMatrix::zeros(NROWS, NCOLS);
+            let mut gpu_result_inplace = DMatrix::zeros(NROWS, NCOLS);
+            backend
+                .slow_read_buffer(gpu_out.buffer(), gpu_result.as_mut_slice())
+                .await
+                .unwrap();
+            backend
+                .slow_read_buffer(gpu_inplace.buffer(), gpu_result_inplace.as_mut_slice())
+                .await
+                .unwrap();
+
+            let mut cpu_result = input.clone();
+            for mut col in cpu_result.column_iter_mut() {
+                let sm = cpu_softmax(variant, &col.clone_owned());
+                col.copy_from(&sm);
+            }
+
+            let mut cpu_result_inplace = input.clone();
+            for mut row in cpu_result_inplace.row_iter_mut() {
+                let masked = (row.transpose() + mask.transpose()).clone_owned();
+                let sm = cpu_softmax(variant, &masked);
+                row.copy_from(&sm.transpose());
+            }
+
+            assert_relative_eq!(gpu_result, cpu_result, epsilon = 1.0e-5);
+            assert_relative_eq!(gpu_result_inplace, cpu_result_inplace, epsilon = 1.0e-5);
+        }
+
+        // More lines than `MAX_NUM_WORKGROUPS` to check the capped grid.
+        const NLINES: usize = 70_001;
+        let input = DMatrix::<f32>::new_random(2, NLINES);
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let gpu_input = GpuTensor::matrix(&backend, &input, usage).unwrap();
+        let gpu_out = GpuTensor::matrix(&backend, &DMatrix::zeros(2, NLINES), usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        softmax
+            .launch(
+                &backend,
+                &mut shapes,
+                &mut pass,
+                SoftmaxVariant::Softmax,
+                0,
+                &gpu_out,
+                &gpu_input,
+                None,
+            )
+            .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+        backend.submit(encoder).unwrap();
+
+        let mut gpu_result = DMatrix::zeros(2, NLINES);
+        backend
+            .slow_read_buffer(gpu_out.buffer(), gpu_result.as_mut_slice())
+            .await
+            .unwrap();
+        let mut cpu_result = input.clone();
+        for mut col in cpu_result.column_iter_mut() {
+            let sm = cpu_softmax(SoftmaxVariant::Softmax, &col.clone_owned());
+            col.copy_from(&sm);
+        }
+        // NOTE: don't use assert_relative_eq so it doesn't print out the whole matrices.
+        assert!(gpu_result.relative_eq(&cpu_result, 1.0e-5, 1.0e-5));
+    }
+}
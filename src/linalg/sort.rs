@@ -0,0 +1,674 @@
+use crate::linalg::{MAX_NUM_WORKGROUPS, Scan, ScanVariant};
+use crate::shapes::{MatrixOrdering, ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use bytemuck::NoUninit;
+use slang_hal::backend::{Backend, DeviceValue};
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+const WORKGROUP_SIZE: u32 = 256;
+const RADIX_BITS: u32 = 4;
+const RADIX: u32 = 1 << RADIX_BITS;
+// Alignment (in number of elements) of each part of the workspace. This matches the most
+// restrictive storage buffer offset alignment (256 bytes) for 4-bytes elements.
+const WORKSPACE_ALIGN: u32 = 64;
+
+/// Key types supported by the [`Sort`] kernel.
+///
+/// This is implemented for `f32` and `u32`.
+pub trait SortKey: DeviceValue + NoUninit {
+    #[doc(hidden)]
+    fn sort_functions<B: Backend>(sort: &Sort<B>) -> (&GpuFunction<B>, &GpuFunction<B>);
+}
+
+impl SortKey for f32 {
+    fn sort_functions<B: Backend>(sort: &Sort<B>) -> (&GpuFunction<B>, &GpuFunction<B>) {
+        (&sort.init_f32, &sort.finalize_f32)
+    }
+}
+
+impl SortKey for u32 {
+    fn sort_functions<B: Backend>(sort: &Sort<B>) -> (&GpuFunction<B>, &GpuFunction<B>) {
+        (&sort.init_u32, &sort.finalize_u32)
+    }
+}
+
+/// Slang module for sorting tensors along an axis with a radix sort.
+///
+/// Each line along the sorted axis is sorted independently. The sort is stable: equal keys keep
+/// their relative order (in ascending and descending order).
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::sort")]
+pub struct Sort<B: Backend> {
+    /// Kernel initializing the `f32` keys to sort.
+    pub init_f32: GpuFunction<B>,
+    /// Kernel initializing the `u32` keys to sort.
+    pub init_u32: GpuFunction<B>,
+    /// Kernel computing the digit histogram of each block of keys.
+    pub histogram: GpuFunction<B>,
+    /// Kernel moving each key to its sorted position for the current digit.
+    pub scatter: GpuFunction<B>,
+    /// Kernel writing the sorted `f32` keys.
+    pub finalize_f32: GpuFunction<B>,
+    /// Kernel writing the sorted `u32` keys.
+    pub finalize_u32: GpuFunction<B>,
+    /// Kernel writing the original position of each sorted key.
+    pub finalize_indices: GpuFunction<B>,
+    /// Kernel writing the payload associated to each sorted key.
+    pub finalize_payload: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct SortParams {
+    shift: u32,
+    line_pass: u32,
+    nrows: u32,
+    len: u32,
+    descending: u32,
+    num_blocks: u32,
+    padding: [u32; 2],
+}
+
+#[derive(ShaderArgs)]
+struct InitArgs<'a, T: DeviceValue, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_input: &'a B::Buffer<ViewShape>,
+    input: B::BufferSlice<'a, T>,
+    keys: B::BufferSlice<'a, u32>,
+    indices: B::BufferSlice<'a, u32>,
+}
+
+#[derive(ShaderArgs)]
+struct RadixArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    keys_src: B::BufferSlice<'a, u32>,
+    indices_src: B::BufferSlice<'a, u32>,
+    hist: Option<B::BufferSlice<'a, u32>>,
+    offsets: Option<B::BufferSlice<'a, u32>>,
+    keys_dst: Option<B::BufferSlice<'a, u32>>,
+    indices_dst: Option<B::BufferSlice<'a, u32>>,
+}
+
+#[derive(ShaderArgs)]
+struct FinalizeArgs<'a, T: DeviceValue, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_out: &'a B::Buffer<ViewShape>,
+    shape_payload: Option<&'a B::Buffer<ViewShape>>,
+    keys: Option<B::BufferSlice<'a, u32>>,
+    indices: Option<B::BufferSlice<'a, u32>>,
+    payload: Option<B::BufferSlice<'a, u32>>,
+    out: B::BufferSlice<'a, T>,
+}
+
+/// Offsets of each part of the sort workspace.
+struct WorkspaceLayout {
+    keys: [u32; 2],
+    indices: [u32; 2],
+    hist: u32,
+    offsets: u32,
+    scan: u32,
+    len: u32,
+}
+
+impl WorkspaceLayout {
+    fn new<B: Backend>(scan: &Scan<B>, len: u32) -> Self {
+        let hist_len = RADIX * len.div_ceil(WORKGROUP_SIZE);
+        let aligned_len = len.next_multiple_of(WORKSPACE_ALIGN);
+        let aligned_hist_len = hist_len.next_multiple_of(WORKSPACE_ALIGN);
+        let hist_shape = ViewShape::contiguous([hist_len, 1, 1, 1], MatrixOrdering::ColumnMajor);
+
+        let keys = [0, aligned_len];
+        let indices = [aligned_len * 2, aligned_len * 3];
+        let hist = aligned_len * 4;
+        let offsets = hist + aligned_hist_len;
+        let scan_offset = offsets + aligned_hist_len;
+        Self {
+            keys,
+            indices,
+            hist,
+            offsets,
+            scan: scan_offset,
+            len: scan_offset + scan.workspace_len(hist_shape, 0),
+        }
+    }
+}
+
+impl<B: Backend> Sort<B> {
+    /// The minimum number of elements of the workspace needed for sorting a tensor with the
+    /// given `shape`.
+    pub fn workspace_len(&self, scan: &Scan<B>, shape: ViewShape) -> u32 {
+        WorkspaceLayout::new(scan, shape.len() as u32).len
+    }
+
+    /// Launches the kernels sorting `keys` along `axis`, and writing the result to `out_keys`.
+    ///
+    /// If `payload` is provided as `(out_payload, payload)`, each element of `payload` is moved
+    /// to `out_payload` at the same position as its associated key. The `workspace` must be
+    /// contiguous and have at least [`Self::workspace_len`] elements.
+    pub fn launch_sort<'a, T: SortKey>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        scan: &Scan<B>,
+        axis: usize,
+        descending: bool,
+        out_keys: impl Into<GpuTensorView<'a, T, B>>,
+        keys: impl Into<GpuTensorView<'a, T, B>>,
+        payload: Option<(GpuTensorView<'a, u32, B>, GpuTensorView<'a, u32, B>)>,
+        workspace: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        let keys = keys.into();
+        let len = keys.shape().size[axis];
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            scan,
+            axis,
+            descending,
+            len,
+            Some(out_keys.into()),
+            None,
+            keys,
+            payload,
+            workspace.into(),
+        )
+    }
+
+    /// Launches the kernels computing the indices that sort `keys` along `axis`.
+    ///
+    /// The `i`-th element of each line of `out_indices` is set to the position, along its line,
+    /// of the `i`-th smallest (or largest if `descending` is `true`) key. The `workspace` must be
+    /// contiguous and have at least [`Self::workspace_len`] elements.
+    pub fn launch_argsort<'a, T: SortKey>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        scan: &Scan<B>,
+        axis: usize,
+        descending: bool,
+        out_indices: impl Into<GpuTensorView<'a, u32, B>>,
+        keys: impl Into<GpuTensorView<'a, T, B>>,
+        workspace: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        let keys = keys.into();
+        let len = keys.shape().size[axis];
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            scan,
+            axis,
+            descending,
+            len,
+            None,
+            Some(out_indices.into()),
+            keys,
+            None,
+            workspace.into(),
+        )
+    }
+
+    /// Launches the kernels computing the `k` largest elements of `keys` along `axis`.
+    ///
+    /// The `k` largest values of each line are written, in descending order, to `out_values`, and
+    /// their positions along the line to `out_indices`. Both must have the same shape as `keys`,
+    /// except along `axis` where their size must be `k`. The `workspace` must be contiguous and
+    /// have at least [`Self::workspace_len`] elements (computed from the shape of `keys`).
+    pub fn launch_top_k<'a, T: SortKey>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        scan: &Scan<B>,
+        axis: usize,
+        k: u32,
+        out_values: impl Into<GpuTensorView<'a, T, B>>,
+        out_indices: impl Into<GpuTensorView<'a, u32, B>>,
+        keys: impl Into<GpuTensorView<'a, T, B>>,
+        workspace: impl Into<GpuTensorView<'a, u32, B>>,
+    ) -> Result<(), B::Error> {
+        self.launch_generic(
+            backend,
+            shapes,
+            pass,
+            scan,
+            axis,
+            true,
+            k,
+            Some(out_values.into()),
+            Some(out_indices.into()),
+            keys.into(),
+            None,
+            workspace.into(),
+        )
+    }
+
+    fn launch_generic<T: SortKey>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        scan: &Scan<B>,
+        axis: usize,
+        descending: bool,
+        k: u32,
+        out_keys: Option<GpuTensorView<T, B>>,
+        out_indices: Option<GpuTensorView<u32, B>>,
+        keys: GpuTensorView<T, B>,
+        payload: Option<(GpuTensorView<u32, B>, GpuTensorView<u32, B>)>,
+        workspace: GpuTensorView<u32, B>,
+    ) -> Result<(), B::Error> {
+        assert!(axis < 4, "Sort: the axis must be smaller than 4.");
+
+        // The kernels always operate along the first dimension.
+        let shape_keys = keys.shape().swap_dims(0, axis);
+        let nrows = shape_keys.size[0];
+        assert!(
+            k <= nrows,
+            "Sort: cannot select more elements than the line length."
+        );
+        let mut size_out = shape_keys.size;
+        size_out[0] = k;
+
+        let out_shapes = out_keys
+            .iter()
+            .map(|out| out.shape())
+            .chain(out_indices.iter().map(|out| out.shape()))
+            .chain(payload.iter().map(|(out, _)| out.shape()));
+        for shape_out in out_shapes {
+            assert_eq!(
+                shape_out.swap_dims(0, axis).size,
+                size_out,
+                "Sort: unexpected output shape."
+            );
+        }
+        if let Some((_, payload)) = &payload {
+            assert_eq!(
+                payload.shape().size,
+                keys.shape().size,
+                "Sort: the payload and keys shapes must be equal."
+            );
+        }
+
+        let len = shape_keys.len() as u32;
+        if len == 0 || k == 0 {
+            return Ok(());
+        }
+
+        let layout = WorkspaceLayout::new(scan, len);
+        assert!(
+            workspace.len() >= layout.len as u64,
+            "Sort: the workspace must have at least {} elements.",
+            layout.len
+        );
+        assert!(
+            workspace.is_contiguous().is_some(),
+            "Sort: the workspace must be contiguous."
+        );
+
+        let num_blocks = len.div_ceil(WORKGROUP_SIZE);
+        let hist_len = num_blocks * RADIX;
+        let ws_keys = layout
+            .keys
+            .map(|offset| workspace.view(offset, [len], [None]));
+        let ws_indices = layout
+            .indices
+            .map(|offset| workspace.view(offset, [len], [None]));
+        let ws_hist = workspace.view(layout.hist, [hist_len], [None]);
+        let ws_offsets = workspace.view(layout.offsets, [hist_len], [None]);
+        let ws_scan = workspace.view(layout.scan, [layout.len - layout.scan], [None]);
+
+        // The keys are sorted first, then the line indices.
+        let num_lines = len / nrows;
+        let line_bits = u32::BITS - (num_lines - 1).leading_zeros();
+        let key_passes = (0..u32::BITS / RADIX_BITS).map(|i| (i * RADIX_BITS, false));
+        let line_passes = (0..line_bits.div_ceil(RADIX_BITS)).map(|i| (i * RADIX_BITS, true));
+        let pass_params: Vec<_> = key_passes
+            .chain(line_passes)
+            .map(|(shift, line_pass)| SortParams {
+                shift,
+                line_pass: line_pass as u32,
+                nrows,
+                len,
+                descending: descending as u32,
+                num_blocks,
+                padding: [0; 2],
+            })
+            .collect();
+        let params = pass_params[0];
+
+        let shape_payload = payload
+            .as_ref()
+            .map(|(_, payload)| payload.shape().swap_dims(0, axis));
+        let out_shape = |view_shape: ViewShape| view_shape.swap_dims(0, axis);
+
+        shapes.insert(backend, shape_keys)?;
+        for view in out_keys.iter().map(|out| out.shape()) {
+            shapes.insert(backend, out_shape(view))?;
+        }
+        for view in out_indices.iter().map(|out| out.shape()) {
+            shapes.insert(backend, out_shape(view))?;
+        }
+        if let Some((out, _)) = &payload {
+            shapes.insert(backend, out_shape(out.shape()))?;
+        }
+        if let Some(shape_payload) = shape_payload {
+            shapes.insert(backend, shape_payload)?;
+        }
+        for params in &pass_params {
+            shapes.insert_params(backend, *params)?;
+        }
+
+        let (init, finalize) = T::sort_functions(self);
+        let radix_grid = [num_blocks.min(MAX_NUM_WORKGROUPS), 1, 1];
+
+        /*
+         * Initialize the keys and indices.
+         */
+        let init_args = InitArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape_input: shapes.get(shape_keys).unwrap_or_else(|| unreachable!()),
+            input: keys.buffer(),
+            keys: ws_keys[0].buffer(),
+            indices: ws_indices[0].buffer(),
+        };
+        init.launch_capped(backend, pass, &init_args, len)?;
+
+        /*
+         * Radix passes.
+         */
+        for (i, params) in pass_params.iter().enumerate() {
+            let src = i % 2;
+            let dst = 1 - src;
+
+            let hist_args = RadixArgs {
+                params: shapes.get_params(*params).unwrap_or_else(|| unreachable!()),
+                keys_src: ws_keys[src].buffer(),
+                indices_src: ws_indices[src].buffer(),
+                hist: Some(ws_hist.buffer()),
+                offsets: None,
+                keys_dst: None,
+                indices_dst: None,
+            };
+            self.histogram
+                .launch_grid(backend, pass, &hist_args, radix_grid)?;
+
+            scan.launch(
+                backend,
+                shapes,
+                pass,
+                ScanVariant::Sum,
+                true,
+                0,
+                ws_offsets,
+                ws_hist,
+                ws_scan,
+            )?;
+
+            let scatter_args = RadixArgs {
+                params: shapes.get_params(*params).unwrap_or_else(|| unreachable!()),
+                keys_src: ws_keys[src].buffer(),
+                indices_src: ws_indices[src].buffer(),
+                hist: None,
+                offsets: Some(ws_offsets.buffer()),
+                keys_dst: Some(ws_keys[dst].buffer()),
+                indices_dst: Some(ws_indices[dst].buffer()),
+            };
+            self.scatter
+                .launch_grid(backend, pass, &scatter_args, radix_grid)?;
+        }
+
+        /*
+         * Write the results.
+         */
+        let result = pass_params.len() % 2;
+        let params = shapes.get_params(params).unwrap_or_else(|| unreachable!());
+
+        if let Some(out_keys) = out_keys {
+            let shape_out = out_shape(out_keys.shape());
+            let args = FinalizeArgs {
+                params,
+                shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+                shape_payload: None,
+                keys: Some(ws_keys[result].buffer()),
+                indices: None,
+                payload: None,
+                out: out_keys.buffer(),
+            };
+            finalize.launch_capped(backend, pass, &args, shape_out.len() as u32)?;
+        }
+
+        if let Some(out_indices) = out_indices {
+            let shape_out = out_shape(out_indices.shape());
+            let args = FinalizeArgs {
+                params,
+                shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+                shape_payload: None,
+                keys: None,
+                indices: Some(ws_indices[result].buffer()),
+                payload: None,
+                out: out_indices.buffer(),
+            };
+            self.finalize_indices
+                .launch_capped(backend, pass, &args, shape_out.len() as u32)?;
+        }
+
+        if let (Some((out_payload, payload)), Some(shape_payload)) = (payload, shape_payload) {
+            let shape_out = out_shape(out_payload.shape());
+            let args = FinalizeArgs {
+                params,
+                shape_out: shapes.get(shape_out).unwrap_or_else(|| unreachable!()),
+                shape_payload: Some(shapes.get(shape_payload).unwrap_or_else(|| unreachable!())),
+                keys: None,
+                indices: Some(ws_indices[result].buffer()),
+                payload: Some(payload.buffer()),
+                out: out_payload.buffer(),
+            };
+            self.finalize_payload
+                .launch_capped(backend, pass, &args, shape_out.len() as u32)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::linalg::Scan;
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::GpuTensor;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_sort_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_sort_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_sort_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_sort_generic(backend).await;
+    }
+
+    async fn gpu_sort_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let sort = super::Sort::from_backend(&backend, &compiler).unwrap();
+        let scan = Scan::from_backend(&backend, &compiler).unwrap();
+
+        // NOTE: the length is large enough to need several levels of scans on the histogram.
+        const LEN: usize = 100_003;
+        const NROWS: usize = 301;
+        const NCOLS: usize = 37;
+        const K: usize = 5;
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+
+        /*
+         * Ascending sort and argsort of a long f32 vector, with duplicates and negative values.
+         */
+        let values: Vec<f32> = (0..LEN)
+            .map(|i| ((i * 7919) % 1013) as f32 - 500.0)
+            .collect();
+        let gpu_values = GpuTensor::vector(&backend, &values, usage).unwrap();
+        let gpu_sorted = GpuTensor::vector(&backend, vec![0.0f32; LEN], usage).unwrap();
+        let gpu_argsort = GpuTensor::vector(&backend, vec![0u32; LEN], usage).unwrap();
+        let workspace_len = sort.workspace_len(&scan, gpu_values.as_view().shape());
+        let gpu_workspace_f32 =
+            GpuTensor::vector(&backend, vec![0u32; workspace_len as usize], usage).unwrap();
+
+        /*
+         * Descending u32 sort of a vector, with a payload.
+         */
+        let keys: Vec<u32> = (0..LEN)
+            .map(|i| (i as u32).wrapping_mul(2654435761))
+            .collect();
+        let payload: Vec<u32> = (0..LEN as u32).collect();
+        let gpu_keys = GpuTensor::vector(&backend, &keys, usage).unwrap();
+        let gpu_payload = GpuTensor::vector(&backend, &payload, usage).unwrap();
+        let gpu_sorted_keys = GpuTensor::vector(&backend, vec![0u32; LEN], usage).unwrap();
+        let gpu_sorted_payload = GpuTensor::vector(&backend, vec![0u32; LEN], usage).unwrap();
+        let gpu_workspace_u32 =
+            GpuTensor::vector(&backend, vec![0u32; workspace_len as usize], usage).unwrap();
+
+        /*
+         * Top-k along the rows of a matrix.
+         */
+        let matrix = DMatrix::<f32>::new_random(NROWS, NCOLS);
+        let gpu_matrix = GpuTensor::matrix(&backend, &matrix, usage).unwrap();
+        let gpu_top_values =
+            GpuTensor::matrix(&backend, &DMatrix::<f32>::zeros(K, NCOLS), usage).unwrap();
+        let gpu_top_indices =
+            GpuTensor::matrix(&backend, &DMatrix::<u32>::zeros(K, NCOLS), usage).unwrap();
+        let workspace_len = sort.workspace_len(&scan, gpu_matrix.as_view().shape());
+        let gpu_workspace_top_k =
+            GpuTensor::vector(&backend, vec![0u32; workspace_len as usize], usage).unwrap();
+
+        let mut shapes = ViewShapeBuffers::new(&backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        sort.launch_sort(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            &scan,
+            0,
+            false,
+            &gpu_sorted,
+            &gpu_values,
+            None,
+            &gpu_workspace_f32,
+        )
+        .unwrap();
+        sort.launch_argsort(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            &scan,
+            0,
+            false,
+            &gpu_argsort,
+            &gpu_values,
+            &gpu_workspace_f32,
+        )
+        .unwrap();
+        sort.launch_sort(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            &scan,
+            0,
+            true,
+            &gpu_sorted_keys,
+            &gpu_keys,
+            Some((gpu_sorted_payload.as_view(), gpu_payload.as_view())),
+            &gpu_workspace_u32,
+        )
+        .unwrap();
+        sort.launch_top_k(
+            &backend,
+            &mut shapes,
+            &mut pass,
+            &scan,
+            0,
+            K as u32,
+            &gpu_top_values,
+            &gpu_top_indices,
+            &gpu_matrix,
+            &gpu_workspace_top_k,
+        )
+        .unwrap();
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+
+        backend.submit(encoder).unwrap();
+
+        let mut sorted = vec![0.0f32; LEN];
+        let mut argsort = vec![0u32; LEN];
+        let mut sorted_keys = vec![0u32; LEN];
+        let mut sorted_payload = vec![0u32; LEN];
+        let mut top_values = DMatrix::<f32>::zeros(K, NCOLS);
+        let mut top_indices = DMatrix::<u32>::zeros(K, NCOLS);
+        backend
+            .slow_read_buffer(gpu_sorted.buffer(), &mut sorted)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_argsort.buffer(), &mut argsort)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_sorted_keys.buffer(), &mut sorted_keys)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_sorted_payload.buffer(), &mut sorted_payload)
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_top_values.buffer(), top_values.as_mut_slice())
+            .await
+            .unwrap();
+        backend
+            .slow_read_buffer(gpu_top_indices.buffer(), top_indices.as_mut_slice())
+            .await
+            .unwrap();
+
+        // NOTE: the sort is stable so the argsort must match exactly.
+        let mut cpu_argsort: Vec<u32> = (0..LEN as u32).collect();
+        cpu_argsort.sort_by(|a, b| values[*a as usize].total_cmp(&values[*b as usize]));
+        let cpu_sorted: Vec<f32> = cpu_argsort.iter().map(|i| values[*i as usize]).collect();
+        assert_eq!(sorted, cpu_sorted);
+        assert_eq!(argsort, cpu_argsort);
+
+        let mut cpu_sorted_payload = payload.clone();
+        cpu_sorted_payload.sort_by(|a, b| keys[*b as usize].cmp(&keys[*a as usize]));
+        let cpu_sorted_keys: Vec<u32> = cpu_sorted_payload
+            .iter()
+            .map(|i| keys[*i as usize])
+            .collect();
+        assert_eq!(sorted_keys, cpu_sorted_keys);
+        assert_eq!(sorted_payload, cpu_sorted_payload);
+
+        for j in 0..NCOLS {
+            let mut cpu_indices: Vec<u32> = (0..NROWS as u32).collect();
+            cpu_indices
+                .sort_by(|a, b| matrix[(*b as usize, j)].total_cmp(&matrix[(*a as usize, j)]));
+            for r in 0..K {
+                assert_eq!(top_indices[(r, j)], cpu_indices[r]);
+                assert_eq!(top_values[(r, j)], matrix[(cpu_indices[r] as usize, j)]);
+            }
+        }
+    }
+}
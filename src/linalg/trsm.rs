@@ -0,0 +1,300 @@
+use crate::linalg::{MAX_NUM_WORKGROUPS, MatrixMode};
+use crate::shapes::{ViewShape, ViewShapeBuffers};
+use crate::tensor::GpuTensorView;
+use slang_hal::backend::Backend;
+use slang_hal::function::GpuFunction;
+use slang_hal::{Shader, ShaderArgs};
+
+/// The triangle of a matrix read by a triangular solve.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Triangle {
+    /// The matrix is lower-triangular.
+    Lower,
+    /// The matrix is upper-triangular.
+    Upper,
+}
+
+impl Triangle {
+    /// The other triangle, i.e., the one containing the transpose of this triangle.
+    pub fn flipped(self) -> Self {
+        match self {
+            Self::Lower => Self::Upper,
+            Self::Upper => Self::Lower,
+        }
+    }
+}
+
+/// The diagonal of a matrix read by a triangular solve.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Diagonal {
+    /// The diagonal is assumed to contain only ones, and isn’t read.
+    Unit,
+    /// The diagonal is read from the matrix.
+    NonUnit,
+}
+
+/// Slang module for solving triangular linear systems.
+///
+/// The systems are batched along the third and fourth dimensions of the tensors, and can be
+/// arbitrary strided views. Only the selected triangle of the matrices is read.
+#[derive(Shader)]
+#[shader(module = "stensor::linalg::trsm")]
+pub struct Trsm<B: Backend> {
+    /// Kernel solving triangular systems with multiple right-hand sides.
+    pub trsm: GpuFunction<B>,
+}
+
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct TrsmParams {
+    lower: u32,
+    unit_diagonal: u32,
+    padding: [u32; 2],
+}
+
+#[derive(ShaderArgs)]
+struct TrsmArgs<'a, B: Backend> {
+    params: &'a B::Buffer<u32>,
+    shape_a: &'a B::Buffer<ViewShape>,
+    shape_b: &'a B::Buffer<ViewShape>,
+    a: B::BufferSlice<'a, f32>,
+    b: B::BufferSlice<'a, f32>,
+}
+
+impl<B: Backend> Trsm<B> {
+    /// Solves, in-place, `op(a) * y = x` where `a` is triangular and `x` a vector. The solution
+    /// `y` overwrites `x`.
+    ///
+    /// This is [`Self::trsm`] with a single right-hand side: `x` must have exactly one column.
+    #[allow(clippy::too_many_arguments)]
+    pub fn trsv<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        triangle: Triangle,
+        mode: MatrixMode,
+        diagonal: Diagonal,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        x: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let x = x.into();
+        assert_eq!(
+            x.shape().size[1],
+            1,
+            "Trsm: the vector must have one column."
+        );
+        self.trsm(backend, shapes, pass, triangle, mode, diagonal, a, x)
+    }
+
+    /// Solves, in-place, `op(a) * x = b` where `a` is triangular, and `b` has multiple
+    /// right-hand sides. The solution overwrites `b`.
+    ///
+    /// Here, `op(a)` is `a` if `mode` is [`MatrixMode::Normal`], or its transpose if it is
+    /// [`MatrixMode::Transposed`]. The `triangle` designates the triangle of `a` itself, not of
+    /// its transpose. Each square matrix of `a`, of shape `[n, n, nmats, ncubes]`, is applied to
+    /// the matrix of `b`, of shape `[n, nrhs, nmats, ncubes]`, with the same index.
+    ///
+    /// The solution is unspecified if a diagonal element read from `a` is zero.
+    #[allow(clippy::too_many_arguments)]
+    pub fn trsm<'a>(
+        &self,
+        backend: &B,
+        shapes: &mut ViewShapeBuffers<B>,
+        pass: &mut B::Pass,
+        triangle: Triangle,
+        mode: MatrixMode,
+        diagonal: Diagonal,
+        a: impl Into<GpuTensorView<'a, f32, B>>,
+        b: impl Into<GpuTensorView<'a, f32, B>>,
+    ) -> Result<(), B::Error> {
+        let mut a = a.into();
+        let b = b.into();
+        let [n, ncols, nmats, ncubes] = a.shape().size;
+        let [b_rows, nrhs, b_mats, b_cubes] = b.shape().size;
+        assert_eq!(n, ncols, "Trsm: the matrices must be square.");
+        assert_eq!(b_rows, n, "Trsm: dimension mismatch.");
+        assert_eq!(
+            (b_mats, b_cubes),
+            (nmats, ncubes),
+            "Trsm: the batch dimensions must match."
+        );
+
+        // Solving with the transpose of a triangle is solving with the other triangle of the
+        // transposed view.
+        let mut triangle = triangle;
+        if mode == MatrixMode::Transposed {
+            a = a.transposed();
+            triangle = triangle.flipped();
+        }
+
+        let num_systems = nrhs * nmats * ncubes;
+        if n == 0 || num_systems == 0 {
+            return Ok(());
+        }
+
+        let params = TrsmParams {
+            lower: (triangle == Triangle::Lower) as u32,
+            unit_diagonal: (diagonal == Diagonal::Unit) as u32,
+            padding: [0; 2],
+        };
+        shapes.insert(backend, a.shape())?;
+        shapes.insert(backend, b.shape())?;
+        shapes.insert_params(backend, params)?;
+        let args = TrsmArgs {
+            params: shapes.get_params(params).unwrap_or_else(|| unreachable!()),
+            shape_a: shapes.get(a.shape()).unwrap_or_else(|| unreachable!()),
+            shape_b: shapes.get(b.shape()).unwrap_or_else(|| unreachable!()),
+            a: a.buffer(),
+            b: b.buffer(),
+        };
+        self.trsm.launch(
+            backend,
+            pass,
+            &args,
+            [num_systems.min(MAX_NUM_WORKGROUPS), 1, 1],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diagonal, Triangle};
+    use crate::linalg::MatrixMode;
+    use crate::shapes::ViewShapeBuffers;
+    use crate::tensor::TensorBuilder;
+    use approx::assert_relative_eq;
+    use minislang::SlangCompiler;
+    use nalgebra::DMatrix;
+    use slang_hal::backend::WebGpu;
+    use slang_hal::backend::{Backend, Encoder};
+    use slang_hal::{BufferUsages, Shader};
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    #[cfg(feature = "cuda")]
+    async fn gpu_trsm_cuda() {
+        let backend = slang_hal::cuda::Cuda::new().unwrap();
+        gpu_trsm_generic(backend).await;
+    }
+
+    #[futures_test::test]
+    #[serial_test::serial]
+    async fn gpu_trsm_webgpu() {
+        let backend = WebGpu::default().await.unwrap();
+        gpu_trsm_generic(backend).await;
+    }
+
+    async fn gpu_trsm_generic(backend: impl Backend) {
+        let mut compiler = SlangCompiler::new(vec![]);
+        crate::register_shaders(&mut compiler);
+        let trsm = super::Trsm::from_backend(&backend, &compiler).unwrap();
+
+        for nrhs in [1, 4] {
+            for triangle in [Triangle::Lower, Triangle::Upper] {
+                for mode in [MatrixMode::Normal, MatrixMode::Transposed] {
+                    for diagonal in [Diagonal::Unit, Diagonal::NonUnit] {
+                        check_trsm(&backend, &trsm, triangle, mode, diagonal, nrhs).await;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn check_trsm<B: Backend>(
+        backend: &B,
+        trsm: &super::Trsm<B>,
+        triangle: Triangle,
+        mode: MatrixMode,
+        diagonal: Diagonal,
+        nrhs: usize,
+    ) {
+        const N: usize = 37;
+        const NMATS: usize = 5;
+        // `b` is a view of the first `N` rows of a taller tensor to test strided right-hand sides.
+        const B_ROWS: usize = N + 3;
+
+        println!("Checking {triangle:?}, {mode:?}, {diagonal:?}, nrhs: {nrhs}.");
+        // Keep the systems well-conditioned by making them diagonally dominant.
+        let a: Vec<_> = (0..NMATS)
+            .map(|_| {
+                DMatrix::<f32>::new_random(N, N).add_scalar(-0.5) / N as f32
+                    + DMatrix::identity(N, N)
+            })
+            .collect();
+        let b: Vec<_> = (0..NMATS)
+            .map(|_| DMatrix::<f32>::new_random(B_ROWS, nrhs))
+            .collect();
+        let a_data: Vec<f32> = a.iter().flat_map(|m| m.iter().copied()).collect();
+        let b_data: Vec<f32> = b.iter().flat_map(|m| m.iter().copied()).collect();
+
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+        let (n, nmats) = (N as u32, NMATS as u32);
+        let gpu_a = TensorBuilder::tensor([n, n, nmats, 1], usage)
+            .build_init(backend, &a_data)
+            .unwrap();
+        let gpu_b = TensorBuilder::tensor([B_ROWS as u32, nrhs as u32, nmats, 1], usage)
+            .build_init(backend, &b_data)
+            .unwrap();
+        let gpu_x = gpu_b.as_view().narrow(0, 0, n);
+
+        let mut shapes = ViewShapeBuffers::new(backend);
+        let mut encoder = backend.begin_encoding();
+        let mut pass = encoder.begin_pass();
+        if nrhs == 1 {
+            trsm.trsv(
+                backend,
+                &mut shapes,
+                &mut pass,
+                triangle,
+                mode,
+                diagonal,
+                &gpu_a,
+                gpu_x,
+            )
+            .unwrap();
+        } else {
+            trsm.trsm(
+                backend,
+                &mut shapes,
+                &mut pass,
+                triangle,
+                mode,
+                diagonal,
+                &gpu_a,
+                gpu_x,
+            )
+            .unwrap();
+        }
+        drop(pass); // Ensure the pass is ended before the encoder is borrowed again.
+        backend.submit(encoder).unwrap();
+
+        let mut x_data = vec![0.0; b_data.len()];
+        backend
+            .slow_read_buffer(gpu_b.buffer(), x_data.as_mut_slice())
+            .await
+            .unwrap();
+
+        for k in 0..NMATS {
+            let mut tri = match triangle {
+                Triangle::Lower => a[k].lower_triangle(),
+                Triangle::Upper => a[k].upper_triangle(),
+            };
+            if diagonal == Diagonal::Unit {
+                tri.fill_diagonal(1.0);
+            }
+            if mode == MatrixMode::Transposed {
+                tri.transpose_mut();
+            }
+
+            let x = DMatrix::from_column_slice(
+                B_ROWS,
+                nrhs,
+                &x_data[k * B_ROWS * nrhs..][..B_ROWS * nrhs],
+            );
+            // The rows past `N` must be left untouched.
+            assert_eq!(x.rows(N, 3), b[k].rows(N, 3));
+            assert_relative_eq!(tri * x.rows(0, N), b[k].rows(0, N), epsilon = 1.0e-4);
+        }
+    }
+}
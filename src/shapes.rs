@@ -1,5 +1,6 @@
 //! Tensor shape definition.
 
+use bytemuck::NoUninit;
 use slang_hal::BufferUsages;
 use slang_hal::backend::Backend;
 use std::collections::HashMap;
@@ -118,6 +119,15 @@ impl ViewShape {
         Self { size, stride }
     }
 
+    /// Swaps the dimensions `i` and `j`.
+    ///
+    /// This is typically used for moving the axis a kernel operates on to the first dimension.
+    pub fn swap_dims(&self, i: usize, j: usize) -> Self {
+        let mut permutations = [0, 1, 2, 3];
+        permutations.swap(i, j);
+        self.permute(permutations)
+    }
+
     /// Attempts to detect the matrix ordering from this shape.
     ///
     /// This check whether two successive elements of the same row (column-major), or the same
@@ -253,12 +263,20 @@ impl ViewShape {
 ///
 /// Ideally, we should use push-constants for view shapes. Unfortunately, push-constants is an
 /// optional extension, so we have to emulate them with uniforms for maximum portability.
+///
+/// The same emulation is available for small plain-old-data kernel parameters (scalar constants,
+/// offsets, etc.) through [`ViewShapeBuffers::insert_params`]. Since these typically change from
+/// one launch to the next, their buffers are temporary and recycled by
+/// [`ViewShapeBuffers::clear_tmp`].
 #[derive(Default)]
 pub struct ViewShapeBuffers<B: Backend> {
     buffers: HashMap<ViewShape, B::Buffer<ViewShape>>,
     tmp_buffers: HashMap<ViewShape, B::Buffer<ViewShape>>,
+    params: HashMap<Vec<u32>, B::Buffer<u32>>,
     // TODO: is this still needed?
     recycled: Mutex<Vec<B::Buffer<ViewShape>>>,
+    // Recycled params buffers, indexed by their length.
+    recycled_params: Mutex<HashMap<usize, Vec<B::Buffer<u32>>>>,
 }
 
 impl<B: Backend> ViewShapeBuffers<B> {
@@ -267,14 +285,24 @@ impl<B: Backend> ViewShapeBuffers<B> {
         Self {
             buffers: HashMap::new(),
             tmp_buffers: HashMap::new(),
+            params: HashMap::new(),
             recycled: Mutex::new(vec![]),
+            recycled_params: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Clears temporary shape buffers and recycles them for reuse.
+    /// Clears temporary shape buffers and kernel parameters buffers, and recycles them for reuse.
+    ///
+    /// This must only be called once the commands using these buffers have been submitted, since
+    /// recycled buffers are overwritten by subsequent insertions.
     pub fn clear_tmp(&mut self) {
         let mut recycled = self.recycled.lock().unwrap();
         recycled.extend(self.tmp_buffers.drain().map(|(_, buf)| buf));
+
+        let mut recycled_params = self.recycled_params.lock().unwrap();
+        for (key, buf) in self.params.drain() {
+            recycled_params.entry(key.len()).or_default().push(buf);
+        }
     }
 
     /// Stores a temporary shape buffer for the given shape, creating one if needed.
@@ -340,4 +368,55 @@ impl<B: Backend> ViewShapeBuffers<B> {
             .get(&shape)
             .or_else(|| self.buffers.get(&shape))
     }
+
+    /// Inserts or retrieves a uniform buffer containing the raw value of the kernel parameters
+    /// `params`.
+    ///
+    /// The buffer content is padded with zeros to a multiple of 16 bytes. The size of `P` must
+    /// be a multiple of 4 bytes. The buffer is temporary: it is recycled by [`Self::clear_tmp`].
+    pub fn insert_params<P: NoUninit>(
+        &mut self,
+        backend: &B,
+        params: P,
+    ) -> Result<&mut B::Buffer<u32>, B::Error> {
+        let buf = match self.params.entry(Self::params_key(&params)) {
+            Entry::Vacant(e) => {
+                let mut recycled = self.recycled_params.lock().unwrap();
+                let buffer = if let Some(mut buffer) = recycled
+                    .get_mut(&e.key().len())
+                    .and_then(|buffers| buffers.pop())
+                {
+                    backend.write_buffer(&mut buffer, 0, e.key())?;
+                    buffer
+                } else {
+                    backend.init_buffer(e.key(), BufferUsages::UNIFORM | BufferUsages::COPY_DST)?
+                };
+                e.insert(buffer)
+            }
+            Entry::Occupied(e) => e.into_mut(),
+        };
+        Ok(buf)
+    }
+
+    /// Gets the gpu uniform `Buffer` containing the value of the kernel parameters `params`.
+    ///
+    /// Returns `None` if it doesn't exist.
+    pub fn get_params<P: NoUninit>(&self, params: P) -> Option<&B::Buffer<u32>> {
+        self.params.get(&Self::params_key(&params))
+    }
+
+    fn params_key<P: NoUninit>(params: &P) -> Vec<u32> {
+        let bytes = bytemuck::bytes_of(params);
+        assert_eq!(
+            bytes.len() % 4,
+            0,
+            "Kernel parameters size must be a multiple of 4 bytes."
+        );
+        let mut words: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|w| u32::from_ne_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        words.resize(words.len().next_multiple_of(4), 0);
+        words
+    }
 }
@@ -549,7 +549,12 @@ impl<'a, T: DeviceValue, B: Backend> GpuTensorView<'a, T, B> {
         self.view_shape
     }
 
-    /// The view’s buffer.
+    /// The view’s buffer, starting at the view’s first element.
+    ///
+    /// Binding this slice requires [`Self::offset`] to match the backend’s storage buffer offset
+    /// alignment (256 bytes on WebGPU), which views created by [`Self::narrow`], [`Self::split`],
+    /// or [`Self::chunk`] generally don’t. Kernels accepting such views bind [`Self::raw_buffer`]
+    /// and take [`Self::offset`] as a parameter instead.
     pub fn buffer(&self) -> B::BufferSlice<'_, T> {
         self.buffer.slice(self.offset as usize..)
     }
@@ -559,6 +564,11 @@ impl<'a, T: DeviceValue, B: Backend> GpuTensorView<'a, T, B> {
         self.buffer
     }
 
+    /// The index, in [`Self::raw_buffer`], of the first element of this view.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
     /// Is this view empty?
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -744,6 +754,69 @@ impl<'a, T: DeviceValue, B: Backend> GpuTensorView<'a, T, B> {
     pub fn row(&self, row: u32) -> Self {
         self.rows(row, 1)
     }
+
+    /// Returns a view containing `len` elements along the dimension `axis`, starting from `start`.
+    ///
+    /// This is a generalization of [`Self::rows`] and [`Self::columns`] to any dimension.
+    pub fn narrow(&self, axis: usize, start: u32, len: u32) -> Self {
+        assert!(axis < 4, "Invalid axis {axis}.");
+        assert!(
+            start + len <= self.view_shape.size[axis],
+            "Out of bounds narrowing: {start}..{} on dimension {axis} of size {}",
+            start + len,
+            self.view_shape.size[axis]
+        );
+        let mut size = self.view_shape.size;
+        size[axis] = len;
+        GpuTensorView {
+            view_shape: ViewShape {
+                size,
+                stride: self.view_shape.stride,
+            },
+            offset: self.offset + self.view_shape.stride[axis] * start,
+            buffer: self.buffer,
+        }
+    }
+
+    /// Splits this view into consecutive views along the dimension `axis`.
+    ///
+    /// The `i`-th returned view has a size of `sizes[i]` along `axis`. The sum of `sizes` must
+    /// be equal to the size of `self` along `axis`. This doesn’t copy any data.
+    pub fn split(&self, axis: usize, sizes: &[u32]) -> Vec<Self> {
+        assert!(axis < 4, "Invalid axis {axis}.");
+        assert_eq!(
+            sizes.iter().sum::<u32>(),
+            self.view_shape.size[axis],
+            "The split sizes must add up to the size of the split dimension."
+        );
+        let mut start = 0;
+        sizes
+            .iter()
+            .map(|len| {
+                let view = self.narrow(axis, start, *len);
+                start += *len;
+                view
+            })
+            .collect()
+    }
+
+    /// Splits this view into at most `num_chunks` views of equal size along the dimension `axis`.
+    ///
+    /// Every chunk has a size of `size.div_ceil(num_chunks)` along `axis`, except the last one
+    /// which may be smaller. Because of this rounding, fewer than `num_chunks` views may be
+    /// returned: for example, splitting 10 elements into 6 chunks gives 5 chunks of size 2. This
+    /// doesn’t copy any data.
+    pub fn chunk(&self, axis: usize, num_chunks: u32) -> Vec<Self> {
+        assert!(axis < 4, "Invalid axis {axis}.");
+        assert!(num_chunks > 0, "The number of chunks must not be zero.");
+        let dim = self.view_shape.size[axis];
+        let chunk_len = dim.div_ceil(num_chunks);
+        let sizes: Vec<_> = (0..dim)
+            .step_by(chunk_len.max(1) as usize)
+            .map(|start| chunk_len.min(dim - start))
+            .collect();
+        self.split(axis, &sizes)
+    }
 }
 
 impl<'a, T: DeviceValue, B: Backend> GpuTensorViewMut<'a, T, B> {
@@ -778,10 +851,10 @@ impl<'a, T: DeviceValue, B: Backend> GpuTensorViewMut<'a, T, B> {
         self.view_shape
     }
 
-    // /// The view’s buffer.
-    // pub fn buffer(&mut self) -> B::BufferSliceMut<'_, T> {
-    //     self.buffer.slice_mut(self.offset as usize..)
-    // }
+    /// The view’s buffer.
+    pub fn buffer(&self) -> B::BufferSlice<'_, T> {
+        self.buffer.slice(self.offset as usize..)
+    }
 
     /// The view’s underlying buffer without any offset.
     pub fn raw_buffer(&mut self) -> &mut B::Buffer<T> {
@@ -956,6 +1029,25 @@ impl<T: DeviceValue, B: Backend> GpuTensor<T, B> {
     pub fn rows(&self, first_row: u32, nrows: u32) -> GpuTensorView<'_, T, B> {
         self.as_view().rows(first_row, nrows)
     }
+
+    /// Returns a view containing `len` elements along the dimension `axis`, starting from `start`.
+    pub fn narrow(&self, axis: usize, start: u32, len: u32) -> GpuTensorView<'_, T, B> {
+        self.as_view().narrow(axis, start, len)
+    }
+
+    /// Splits this tensor into consecutive views along the dimension `axis`.
+    ///
+    /// See [`GpuTensorView::split`] for details.
+    pub fn split(&self, axis: usize, sizes: &[u32]) -> Vec<GpuTensorView<'_, T, B>> {
+        self.as_view().split(axis, sizes)
+    }
+
+    /// Splits this tensor into at most `num_chunks` views of equal size along the dimension `axis`.
+    ///
+    /// See [`GpuTensorView::chunk`] for details.
+    pub fn chunk(&self, axis: usize, num_chunks: u32) -> Vec<GpuTensorView<'_, T, B>> {
+        self.as_view().chunk(axis, num_chunks)
+    }
 }
 
 impl<T: DeviceValue + NoUninit, B: Backend> GpuTensor<T, B> {